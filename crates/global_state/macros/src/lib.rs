@@ -1,67 +1,215 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, Data, DataEnum, DeriveInput, Ident, LitFloat, Path, Token,
+};
 
-#[proc_macro_derive(GlobalState)]
-pub fn derive_global_state(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-    let ident = &input.ident;
-    let mut quotes = vec![];
-    if let Data::Enum(enm) = input.data {
-        for variant in enm.variants.iter() {
-            let variant_ident = &variant.ident;
-            quotes.push(quote! {
-                app.add_exit_system(#ident::#variant_ident, global_state::global_cleanup);
-                app.add_exit_system(#ident::#variant_ident, global_state::reset_state_time::<#ident>);
-                app.add_system_set(
-                    iyes_loopless::prelude::ConditionSet::new()
-                        .run_in_state(#ident::#variant_ident)
-                        .with_system(global_state::update_state_time::<#ident>)
-                        .into()
-                );
-            });
+/// Helper attributes, shared by both derives
+///
+/// On the enum: `#[global_state(just_entered = 0.2)]`, seconds during which
+/// `StateTime::just_entered` is true.
+///
+/// On variants: `#[global_state(no_cleanup)]` skips the despawning of entities on exit,
+/// `#[global_state(on_enter = path)]` and `#[global_state(on_exit = path)]` add systems.
+const ATTRIBUTE: &str = "global_state";
+
+enum Arg {
+    NoCleanup(Ident),
+    OnEnter(Ident, Path),
+    OnExit(Ident, Path),
+    JustEntered(Ident, LitFloat),
+}
+
+impl Arg {
+    fn key(&self) -> &Ident {
+        match self {
+            Self::NoCleanup(key)
+            | Self::OnEnter(key, _)
+            | Self::OnExit(key, _)
+            | Self::JustEntered(key, _) => key,
         }
     }
+}
 
-    TokenStream::from(quote! {
-        impl GlobalState for #ident {
-            fn init_global_state(app: &mut bevy::app::App) {
-                app.add_state(#ident::default());
-                app.init_resource::<global_state::StateTime<#ident>>();
-                #(#quotes)*
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+
+        match key.to_string().as_str() {
+            "no_cleanup" => Ok(Self::NoCleanup(key)),
+            "on_enter" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::OnEnter(key, input.parse()?))
+            }
+            "on_exit" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::OnExit(key, input.parse()?))
             }
+            "just_entered" => {
+                input.parse::<Token![=]>()?;
+                Ok(Self::JustEntered(key, input.parse()?))
+            }
+            _ => Err(syn::Error::new_spanned(
+                &key,
+                format!(
+                    "unknown attribute `{key}`, expected one of `no_cleanup`, `on_enter`, `on_exit`, `just_entered`"
+                ),
+            )),
         }
-    })
+    }
 }
 
-#[proc_macro_derive(TransientState)]
-pub fn derive_transient_state(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
+fn parse_args(attrs: &[Attribute]) -> syn::Result<Vec<Arg>> {
+    let mut args = vec![];
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(ATTRIBUTE)) {
+        args.extend(attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?);
+    }
+
+    Ok(args)
+}
+
+/// What differs between the two derives
+struct Kind {
+    trait_name: &'static str,
+    init_fn: &'static str,
+    cleanup: TokenStream2,
+}
+
+fn expand(input: DeriveInput, kind: Kind) -> syn::Result<TokenStream2> {
     let ident = &input.ident;
+    let trait_ident = format_ident!("{}", kind.trait_name);
+    let init_fn = format_ident!("{}", kind.init_fn);
+
+    let variants = match &input.data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("{} can only be derived for enums", kind.trait_name),
+            ))
+        }
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            format!("{} cannot be derived for generic enums", kind.trait_name),
+        ));
+    }
+
+    let mut state_time = quote! {
+        app.init_resource::<global_state::StateTime<#ident>>();
+    };
+
+    for arg in parse_args(&input.attrs)? {
+        match arg {
+            Arg::JustEntered(_, threshold) => {
+                state_time = quote! {
+                    app.insert_resource(global_state::StateTime::<#ident>::with_threshold(#threshold));
+                };
+            }
+            arg => {
+                return Err(syn::Error::new_spanned(
+                    arg.key(),
+                    format!("`{}` can only be set on variants", arg.key()),
+                ))
+            }
+        }
+    }
+
     let mut quotes = vec![];
-    if let Data::Enum(enm) = input.data {
-        for variant in enm.variants.iter() {
-            let variant_ident = &variant.ident;
+
+    for variant in variants.iter() {
+        let variant_ident = &variant.ident;
+        let state = quote!(#ident::#variant_ident);
+        let mut cleanup = true;
+
+        for arg in parse_args(&variant.attrs)? {
+            match arg {
+                Arg::NoCleanup(_) => cleanup = false,
+                Arg::OnEnter(_, system) => quotes.push(quote! {
+                    app.add_enter_system(#state, #system);
+                }),
+                Arg::OnExit(_, system) => quotes.push(quote! {
+                    app.add_exit_system(#state, #system);
+                }),
+                Arg::JustEntered(key, _) => {
+                    return Err(syn::Error::new_spanned(
+                        &key,
+                        "`just_entered` can only be set on the enum",
+                    ))
+                }
+            }
+        }
+
+        if cleanup {
+            let cleanup_system = &kind.cleanup;
             quotes.push(quote! {
-                app.add_exit_system(#ident::#variant_ident, global_state::cleanup_transient);
-                app.add_exit_system(#ident::#variant_ident, global_state::reset_state_time::<#ident>);
-                app.add_system_set(
-                    iyes_loopless::prelude::ConditionSet::new()
-                        .run_in_state(#ident::#variant_ident)
-                        .with_system(global_state::update_state_time::<#ident>)
-                        .into()
-                );
+                app.add_exit_system(#state, #cleanup_system);
             });
         }
+
+        // entities scoped to a variant are despawned even with `no_cleanup`, they opted in
+        quotes.push(quote! {
+            app.add_exit_system(#state, global_state::cleanup_state_scoped::<#ident>);
+            app.add_exit_system(#state, global_state::reset_state_time::<#ident>);
+            app.add_system_set(
+                iyes_loopless::prelude::ConditionSet::new()
+                    .run_in_state(#state)
+                    .with_system(global_state::update_state_time::<#ident>)
+                    .into()
+            );
+        });
     }
 
-    TokenStream::from(quote! {
-        impl TransientState for #ident {
-            fn init_transient_state(app: &mut bevy::app::App) {
+    Ok(quote! {
+        impl global_state::#trait_ident for #ident {
+            fn #init_fn(app: &mut bevy::app::App) {
+                use iyes_loopless::prelude::AppLooplessStateExt as _;
+
                 app.add_state(#ident::default());
-                app.init_resource::<global_state::StateTime<#ident>>();
+                #state_time
                 #(#quotes)*
             }
         }
     })
 }
+
+#[proc_macro_derive(GlobalState, attributes(global_state))]
+pub fn derive_global_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = input.ident.clone();
+
+    expand(
+        input,
+        Kind {
+            trait_name: "GlobalState",
+            init_fn: "init_global_state",
+            cleanup: quote!(global_state::global_cleanup::<#ident>),
+        },
+    )
+    .unwrap_or_else(|err| err.to_compile_error())
+    .into()
+}
+
+#[proc_macro_derive(TransientState, attributes(global_state))]
+pub fn derive_transient_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = input.ident.clone();
+
+    expand(
+        input,
+        Kind {
+            trait_name: "TransientState",
+            init_fn: "init_transient_state",
+            cleanup: quote!(global_state::cleanup_transient::<#ident>),
+        },
+    )
+    .unwrap_or_else(|err| err.to_compile_error())
+    .into()
+}
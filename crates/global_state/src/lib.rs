@@ -3,7 +3,8 @@ use bevy::{
     prelude::*,
 };
 pub use global_state_macros::{GlobalState, TransientState};
-use std::marker::PhantomData;
+use iyes_loopless::state::CurrentState;
+use std::collections::VecDeque;
 
 pub trait GlobalState {
     fn init_global_state(app: &mut App);
@@ -41,49 +42,139 @@ pub struct Persistent;
 #[derive(Component)]
 pub struct Transient;
 
-#[derive(Default, Resource)]
-pub struct StateTime<T>
-where
-    T: Resource + ?Sized,
-{
+/// Ties an entity to one or more variants of the state `T`.
+///
+/// The entity is despawned when one of the variants is exited, and only then: the cleanups of
+/// other states leave it alone.
+#[derive(Component, Debug)]
+pub struct StateScoped<T: StateData>(pub Vec<T>);
+
+impl<T: StateData> StateScoped<T> {
+    pub fn new(state: T) -> Self {
+        Self(vec![state])
+    }
+
+    pub fn any(states: impl IntoIterator<Item = T>) -> Self {
+        Self(states.into_iter().collect())
+    }
+
+    pub fn contains(&self, state: &T) -> bool {
+        self.0.contains(state)
+    }
+}
+
+/// number of exited states `StateTime` remembers
+const HISTORY_LEN: usize = 16;
+
+/// Seconds spent in the current variant of `T`, and in the ones exited before it
+#[derive(Resource)]
+pub struct StateTime<T: StateData> {
     pub time: f32,
-    _phantom: PhantomData<T>,
+    /// seconds during which the state counts as just entered, set with
+    /// `#[global_state(just_entered = ..)]`
+    pub threshold: f32,
+    /// most recently exited first
+    history: VecDeque<(T, f32)>,
+}
+
+impl<T: StateData> Default for StateTime<T> {
+    fn default() -> Self {
+        Self::with_threshold(0.1)
+    }
 }
 
-impl<T> StateTime<T>
-where
-    T: Resource + ?Sized,
-{
+impl<T: StateData> StateTime<T> {
+    pub fn with_threshold(threshold: f32) -> Self {
+        Self {
+            time: 0.,
+            threshold,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
     pub fn just_entered(&self) -> bool {
-        self.time < 0.1
+        self.time < self.threshold
+    }
+
+    /// Exited states with the seconds spent in each, most recent first
+    pub fn history(&self) -> impl Iterator<Item = &(T, f32)> {
+        self.history.iter()
+    }
+
+    /// The state exited last, with the seconds spent in it
+    pub fn previous(&self) -> Option<&(T, f32)> {
+        self.history.front()
+    }
+
+    /// Seconds spent in `state` over the remembered history, the current stay excluded
+    pub fn time_in(&self, state: &T) -> f32 {
+        self.history
+            .iter()
+            .filter(|(exited, _)| exited == state)
+            .map(|(_, time)| time)
+            .sum()
+    }
+
+    fn exit(&mut self, state: T) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_back();
+        }
+
+        self.history.push_front((state, self.time));
+        self.time = 0.;
     }
 }
 
-pub fn reset_state_time<T>(mut state_time: ResMut<StateTime<T>>)
-where
-    T: Resource + ?Sized,
-{
-    state_time.time = 0.;
+/// Record the time spent in the variant of `T` being exited
+pub fn reset_state_time<T: StateData>(
+    mut state_time: ResMut<StateTime<T>>,
+    state: Res<CurrentState<T>>,
+) {
+    // exit systems run before `CurrentState` moves on to the next state
+    state_time.exit(state.0.clone());
 }
 
-pub fn update_state_time<T>(mut state_time: ResMut<StateTime<T>>, time: Res<Time>)
-where
-    T: Resource + ?Sized,
-{
+pub fn update_state_time<T: StateData>(mut state_time: ResMut<StateTime<T>>, time: Res<Time>) {
     state_time.time += time.delta_seconds();
 }
 
-pub fn global_cleanup(
+/// Despawn every root entity which is neither `Persistent` nor scoped to some variants of `T`
+pub fn global_cleanup<T: StateData>(
     mut commands: Commands,
-    query: Query<Entity, (Without<Persistent>, Without<Parent>)>,
+    query: Query<
+        Entity,
+        (
+            Without<Persistent>,
+            Without<Parent>,
+            Without<StateScoped<T>>,
+        ),
+    >,
 ) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-pub fn cleanup_transient(mut commands: Commands, query: Query<Entity, (With<Transient>, Without<Parent>)>) {
+/// Despawn every root entity which is `Transient` and not scoped to some variants of `T`
+pub fn cleanup_transient<T: StateData>(
+    mut commands: Commands,
+    query: Query<Entity, (With<Transient>, Without<Parent>, Without<StateScoped<T>>)>,
+) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
+
+/// Despawn the entities scoped to the variant of `T` being exited
+pub fn cleanup_state_scoped<T: StateData>(
+    mut commands: Commands,
+    state: Res<CurrentState<T>>,
+    query: Query<(Entity, &StateScoped<T>)>,
+) {
+    // exit systems run before `CurrentState` moves on to the next state
+    for (entity, scoped) in query.iter() {
+        if scoped.contains(&state.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use global_state::TransientState;
+
+#[derive(Default, TransientState)]
+enum PauseState<T> {
+    #[default]
+    Off,
+    On(T),
+}
+
+fn main() {
+    let _ = PauseState::<u32>::On(0);
+}
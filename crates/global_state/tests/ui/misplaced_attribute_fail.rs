@@ -0,0 +1,19 @@
+use global_state::GlobalState;
+
+#[derive(Default, GlobalState)]
+enum AppState {
+    #[default]
+    #[global_state(just_entered = 0.2)]
+    Menu,
+}
+
+#[derive(Default, GlobalState)]
+#[global_state(no_cleanup)]
+enum LevelState {
+    #[default]
+    Loading,
+}
+
+fn main() {
+    let _ = (AppState::Menu, LevelState::Loading);
+}
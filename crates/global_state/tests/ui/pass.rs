@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use global_state::{AddGlobalState, AddTransientState, GlobalState, StateTime, TransientState};
+
+fn enter_menu() {}
+
+fn exit_game() {}
+
+#[derive(Clone, Copy, Debug, Default, Eq, GlobalState, Hash, PartialEq, Resource)]
+#[global_state(just_entered = 0.5)]
+enum AppState {
+    #[default]
+    #[global_state(on_enter = enter_menu)]
+    Menu,
+    #[global_state(no_cleanup, on_exit = exit_game)]
+    InGame,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Resource, TransientState)]
+enum PauseState {
+    #[default]
+    Off,
+    #[global_state(no_cleanup)]
+    On,
+}
+
+fn main() {
+    let mut app = App::new();
+    app.add_global_state::<AppState>()
+        .add_transient_state::<PauseState>();
+
+    assert_eq!(app.world.resource::<StateTime<AppState>>().threshold, 0.5);
+    assert_eq!(app.world.resource::<StateTime<PauseState>>().threshold, 0.1);
+}
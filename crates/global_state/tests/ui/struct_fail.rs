@@ -0,0 +1,10 @@
+use global_state::GlobalState;
+
+#[derive(Default, GlobalState)]
+struct AppState {
+    level: u32,
+}
+
+fn main() {
+    let _ = AppState::default().level;
+}
@@ -0,0 +1,12 @@
+use global_state::GlobalState;
+
+#[derive(Default, GlobalState)]
+enum AppState {
+    #[default]
+    #[global_state(cleanup)]
+    Menu,
+}
+
+fn main() {
+    let _ = AppState::Menu;
+}
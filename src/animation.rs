@@ -0,0 +1,447 @@
+//! Sprite sheet animations described by `.anim` files
+//!
+//! An `.anim` file names the sheet image, its grid and the frames of each clip:
+//!
+//! ```ron
+//! (
+//!     image: "images/player.png",
+//!     tile_size: (16., 16.),
+//!     columns: 1,
+//!     rows: 1,
+//!     clips: {
+//!         Idle: (frames: [0], fps: 8.),
+//!         Death: (frames: [0], fps: 8., looping: false),
+//!     },
+//! )
+//! ```
+//!
+//! Clips missing from a file fall back to `Idle`.
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context};
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{
+    npc::Enemy,
+    physics::Velocity,
+    player::{Direction, Health, JumpStatus, Player},
+    state::{AppState, ConditionSet, PauseState},
+    weapon::WeaponCooldown,
+};
+
+/// seconds the hurt clip plays after a hit
+const HURT_TIME: f32 = 0.3;
+
+/// horizontal speed above which an entity runs instead of idling
+const RUN_THRESHOLD: f32 = 10.;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+pub(crate) enum Clip {
+    #[default]
+    Idle,
+    Run,
+    Jump,
+    Fall,
+    WallSlide,
+    Dash,
+    Attack,
+    Hurt,
+    Death,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ClipFrames {
+    /// indices into the sheet, left to right then top to bottom
+    frames: Vec<usize>,
+    fps: f32,
+    #[serde(default = "looping_by_default")]
+    looping: bool,
+}
+
+fn looping_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, TypeUuid)]
+#[uuid = "6f3c1a52-8d0e-4c52-9a8e-2b7f35d1c0a4"]
+pub(crate) struct AnimationSheet {
+    pub(crate) atlas: Handle<TextureAtlas>,
+    clips: HashMap<Clip, ClipFrames>,
+}
+
+impl AnimationSheet {
+    fn clip(&self, clip: Clip) -> Option<&ClipFrames> {
+        self.clips
+            .get(&clip)
+            .or_else(|| self.clips.get(&Clip::Idle))
+            .filter(|clip| !clip.frames.is_empty())
+    }
+}
+
+/// What an `.anim` file contains
+#[derive(Deserialize)]
+struct AnimationSheetFile {
+    image: String,
+    tile_size: (f32, f32),
+    columns: usize,
+    rows: usize,
+    clips: HashMap<Clip, ClipFrames>,
+}
+
+impl AnimationSheetFile {
+    /// `Animation::advance` divides by the fps and indexes the atlas with the frames
+    fn validate(&self) -> anyhow::Result<()> {
+        let frame_count = self.columns * self.rows;
+
+        for (clip, frames) in &self.clips {
+            if !frames.fps.is_finite() || frames.fps <= 0. {
+                bail!("fps of {clip:?} is {}, it must be above 0", frames.fps);
+            }
+
+            if let Some(frame) = frames.frames.iter().find(|&&frame| frame >= frame_count) {
+                bail!(
+                    "frame {frame} of {clip:?} is out of the sheet, which has {frame_count} frames"
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct AnimationSheetLoader;
+
+impl AssetLoader for AnimationSheetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let file: AnimationSheetFile = ron::de::from_bytes(bytes)?;
+            file.validate()
+                .with_context(|| format!("invalid {}", load_context.path().display()))?;
+
+            let image_path = AssetPath::new(PathBuf::from(&file.image), None);
+            let atlas = TextureAtlas::from_grid(
+                load_context.get_handle(image_path.clone()),
+                Vec2::new(file.tile_size.0, file.tile_size.1),
+                file.columns,
+                file.rows,
+                None,
+                None,
+            );
+            let atlas = load_context
+                .set_labeled_asset("atlas", LoadedAsset::new(atlas).with_dependency(image_path));
+
+            load_context.set_default_asset(LoadedAsset::new(AnimationSheet {
+                atlas,
+                clips: file.clips,
+            }));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim"]
+    }
+}
+
+/// The clip an entity plays from its sheet
+#[derive(Clone, Component, Debug, Default)]
+pub(crate) struct Animation {
+    sheet: Handle<AnimationSheet>,
+    clip: Clip,
+    frame: usize,
+    timer: Timer,
+}
+
+impl Animation {
+    pub(crate) fn new(sheet: Handle<AnimationSheet>) -> Self {
+        Self { sheet, ..default() }
+    }
+
+    /// Switch to `clip`, it keeps going if it's already playing
+    fn play(&mut self, clip: Clip) {
+        if self.clip != clip {
+            self.clip = clip;
+            self.frame = 0;
+            self.timer.reset();
+        }
+    }
+
+    fn advance(
+        time: Res<Time>,
+        sheets: Res<Assets<AnimationSheet>>,
+        mut animations: Query<(
+            &mut Animation,
+            &mut TextureAtlasSprite,
+            &mut Handle<TextureAtlas>,
+        )>,
+    ) {
+        for (mut animation, mut sprite, mut atlas) in animations.iter_mut() {
+            let sheet = match sheets.get(&animation.sheet) {
+                Some(sheet) => sheet,
+                None => continue,
+            };
+
+            // entities are spawned before their sheet is there
+            if *atlas != sheet.atlas {
+                *atlas = sheet.atlas.clone();
+            }
+
+            let clip = match sheet.clip(animation.clip) {
+                Some(clip) => clip,
+                None => continue,
+            };
+
+            animation
+                .timer
+                .set_duration(Duration::from_secs_f32(1. / clip.fps));
+            animation.timer.set_mode(TimerMode::Repeating);
+            animation.timer.tick(time.delta());
+
+            let frame = animation.frame + animation.timer.times_finished_this_tick() as usize;
+            animation.frame = if clip.looping {
+                frame % clip.frames.len()
+            } else {
+                frame.min(clip.frames.len() - 1)
+            };
+
+            let index = clip.frames[animation.frame];
+            if sprite.index != index {
+                sprite.index = index;
+            }
+        }
+    }
+}
+
+/// Plays the hurt clip for a moment after the health of an entity went down
+#[derive(Clone, Component, Debug)]
+pub(crate) struct Hurt {
+    last_health: Option<u32>,
+    timer: Timer,
+}
+
+impl Default for Hurt {
+    fn default() -> Self {
+        let duration = Duration::from_secs_f32(HURT_TIME);
+
+        let mut timer = Timer::new(duration, TimerMode::Once);
+        timer.tick(duration);
+
+        Self {
+            last_health: None,
+            timer,
+        }
+    }
+}
+
+impl Hurt {
+    fn is_hurting(&self) -> bool {
+        !self.timer.finished()
+    }
+
+    fn update(time: Res<Time>, mut query: Query<(&Health, &mut Hurt)>) {
+        for (health, mut hurt) in query.iter_mut() {
+            if hurt
+                .last_health
+                .map_or(false, |last_health| health.current < last_health)
+            {
+                hurt.timer.reset();
+            } else {
+                hurt.timer.tick(time.delta());
+            }
+
+            hurt.last_health = Some(health.current);
+        }
+    }
+}
+
+fn animate_player(
+    mut players: Query<(
+        &Player,
+        &Health,
+        &Hurt,
+        &Velocity,
+        Option<&WeaponCooldown>,
+        &mut Animation,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    for (player, health, hurt, velocity, cooldown, mut animation, mut sprite) in players.iter_mut()
+    {
+        let attacking = cooldown.map_or(false, |cooldown| !cooldown.finished());
+
+        let clip = if health.current == 0 {
+            Clip::Death
+        } else if hurt.is_hurting() {
+            Clip::Hurt
+        } else if player.dashing {
+            Clip::Dash
+        } else if player.wall_sliding {
+            Clip::WallSlide
+        } else if attacking {
+            Clip::Attack
+        } else {
+            match player.jump_status {
+                JumpStatus::InitiateJump | JumpStatus::GoingUp | JumpStatus::StoppingUp => {
+                    Clip::Jump
+                }
+                JumpStatus::Falling => Clip::Fall,
+                JumpStatus::WallSliding => Clip::WallSlide,
+                JumpStatus::CanJump if velocity.linvel.x.abs() > RUN_THRESHOLD => Clip::Run,
+                JumpStatus::CanJump => Clip::Idle,
+            }
+        };
+
+        animation.play(clip);
+
+        // neutral keeps the last direction
+        let flip_x = match player.facing_direction {
+            Direction::Left => true,
+            Direction::Right => false,
+            Direction::Neutral => sprite.flip_x,
+        };
+        if sprite.flip_x != flip_x {
+            sprite.flip_x = flip_x;
+        }
+    }
+}
+
+fn animate_mobs(
+    mut mobs: Query<(&Hurt, &Velocity, &mut Animation, &mut TextureAtlasSprite), With<Enemy>>,
+) {
+    for (hurt, velocity, mut animation, mut sprite) in mobs.iter_mut() {
+        let clip = if hurt.is_hurting() {
+            Clip::Hurt
+        } else if velocity.linvel.x.abs() > RUN_THRESHOLD {
+            Clip::Run
+        } else {
+            Clip::Idle
+        };
+
+        animation.play(clip);
+
+        if velocity.linvel.x.abs() > RUN_THRESHOLD {
+            let flip_x = velocity.linvel.x < 0.;
+            if sprite.flip_x != flip_x {
+                sprite.flip_x = flip_x;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SystemLabel)]
+enum Label {
+    Hurt,
+    Clip,
+}
+
+pub(crate) struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AnimationSheet>()
+            .init_asset_loader::<AnimationSheetLoader>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .run_if_not(PauseState::is_paused)
+                    .label(Label::Hurt)
+                    .with_system(Hurt::update)
+                    .into(),
+            )
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .run_if_not(PauseState::is_paused)
+                    .label(Label::Clip)
+                    .after(Label::Hurt)
+                    .with_system(animate_player)
+                    .with_system(animate_mobs)
+                    .into(),
+            )
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .run_if_not(PauseState::is_paused)
+                    .after(Label::Clip)
+                    .with_system(Animation::advance)
+                    .into(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::ControlAction, testing::TestApp};
+
+    fn clip(app: &TestApp, entity: Entity) -> Clip {
+        app.world
+            .get::<Animation>(entity)
+            .expect("entity has no Animation")
+            .clip
+    }
+
+    fn sheet_file(columns: usize, rows: usize, frames: Vec<usize>, fps: f32) -> AnimationSheetFile {
+        AnimationSheetFile {
+            image: "images/player.png".to_string(),
+            tile_size: (16., 16.),
+            columns,
+            rows,
+            clips: HashMap::from([(
+                Clip::Idle,
+                ClipFrames {
+                    frames,
+                    fps,
+                    looping: true,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn player_clip_follows_its_state() {
+        let mut app = TestApp::new();
+        app.spawn_ground(Vec2::new(0., 0.), Vec2::new(400., 8.));
+        let player = app.spawn_player(Vec2::new(0., 24.), 3);
+
+        app.step_seconds(1.);
+        assert_eq!(clip(&app, player), Clip::Idle);
+
+        app.press(player, ControlAction::Right);
+        app.step_seconds(0.2);
+        assert_eq!(clip(&app, player), Clip::Run);
+
+        // a hit plays over running
+        app.world
+            .get_mut::<Health>(player)
+            .expect("player has no Health")
+            .current -= 1;
+        app.step();
+        assert_eq!(clip(&app, player), Clip::Hurt);
+
+        app.step_seconds(HURT_TIME);
+        app.press(player, ControlAction::Jump);
+        app.step_frames(2);
+        assert_eq!(clip(&app, player), Clip::Jump);
+    }
+
+    #[test]
+    fn sheet_with_bad_clips_is_refused() {
+        assert!(sheet_file(2, 2, vec![0, 3], 8.).validate().is_ok());
+        assert!(sheet_file(2, 2, vec![0, 4], 8.).validate().is_err());
+        assert!(sheet_file(2, 2, vec![0], 0.).validate().is_err());
+        assert!(sheet_file(2, 2, vec![0], -8.).validate().is_err());
+    }
+}
@@ -1,83 +1,272 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{FileAssetIo, LoadState},
+    prelude::*,
+    sprite::Anchor,
+};
 use bevy_asset_loader::prelude::*;
+use global_state::StateTime;
 
 use crate::{
+    animation::AnimationSheet,
     audio::AudioSource,
-    state::{AppLooplessStateExt, AppState},
+    state::{AppLooplessStateExt, AppState, ConditionSet, IntoConditionalSystem, NextState},
 };
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct MainMenuAssets {
-    #[asset(key = "main_menu.bgm")]
-    pub(crate) bgm: Handle<AudioSource>,
-}
+pub(crate) mod manifest;
+
+const MANIFEST: &str = "dynamic_asset.assets";
+
+/// seconds a loading screen stays up at least, so quick loads don't flicker
+const MIN_LOADING_TIME: f32 = 0.5;
+
+const BAR_SIZE: Vec2 = Vec2::new(160., 12.);
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct ImageAssets {
-    #[asset(key = "image.player")]
-    pub(crate) player: Handle<Image>,
+/// An `AssetCollection` whose manifest keys are also listed in `KEYS`, for `manifest::check`
+macro_rules! asset_collection {
+    ($name:ident { $($field:ident: $ty:ty = $key:tt),* $(,)? }) => {
+        #[derive(AssetCollection, Resource)]
+        pub(crate) struct $name {
+            $(
+                #[asset(key = $key)]
+                pub(crate) $field: $ty,
+            )*
+        }
+
+        impl $name {
+            const KEYS: &'static [&'static str] = &[$($key),*];
+        }
+    };
 }
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct AudioAssets {
-    #[asset(key = "sounds.bgm")]
-    pub(crate) bgm: Handle<AudioSource>,
+asset_collection!(MainMenuAssets {
+    bgm: Handle<AudioSource> = "main_menu.bgm",
+});
+
+asset_collection!(AnimationAssets {
+    player: Handle<AnimationSheet> = "animation.player",
+    mob: Handle<AnimationSheet> = "animation.mob",
+});
+
+asset_collection!(AudioAssets {
+    bgm: Handle<AudioSource> = "sounds.bgm",
+});
+
+asset_collection!(SfxAssets {
+    jump: Handle<AudioSource> = "sfx.jump",
+    dash: Handle<AudioSource> = "sfx.dash",
+    attack: Handle<AudioSource> = "sfx.attack",
+    hit: Handle<AudioSource> = "sfx.hit",
+    mob_death: Handle<AudioSource> = "sfx.mob_death",
+    check_point: Handle<AudioSource> = "sfx.check_point",
+    click: Handle<AudioSource> = "sfx.click",
+});
+
+asset_collection!(FontAssets {
+    monogram: Handle<Font> = "font.monogram",
+});
+
+/// Every collection loaded from the manifest, with its keys
+const COLLECTIONS: &[(&str, &[&str])] = &[
+    ("MainMenuAssets", MainMenuAssets::KEYS),
+    ("AnimationAssets", AnimationAssets::KEYS),
+    ("AudioAssets", AudioAssets::KEYS),
+    ("SfxAssets", SfxAssets::KEYS),
+    ("FontAssets", FontAssets::KEYS),
+];
+
+/// How far the loading state is, over the assets of the manifest it has asked for
+#[derive(Debug, Default, Resource)]
+pub(crate) struct LoadingProgress {
+    loaded: usize,
+    total: usize,
+    /// path of an asset still loading
+    current: Option<String>,
 }
 
-#[derive(AssetCollection, Resource)]
-pub(crate) struct FontAssets {
-    #[asset(key = "font.monogram")]
-    pub(crate) monogram: Handle<Font>,
+impl LoadingProgress {
+    /// share of loaded assets, from 0 to 1
+    fn share(&self) -> f32 {
+        if self.total == 0 {
+            0.
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.total > 0 && self.loaded == self.total
+    }
+
+    fn update(
+        mut progress: ResMut<LoadingProgress>,
+        asset_server: Res<AssetServer>,
+        manifests: Res<Assets<StandardDynamicAssetCollection>>,
+    ) {
+        let mut loaded = 0;
+        let mut total = 0;
+        let mut current = None;
+
+        let manifest_path = MANIFEST.to_string();
+        let paths = manifests
+            .iter()
+            .flat_map(|(_, manifest)| manifest.0.values())
+            .filter_map(|asset| match asset {
+                StandardDynamicAsset::File { path } => Some(path),
+                _ => None,
+            });
+
+        // assets of the manifest which haven't been asked for belong to other loading states
+        for path in std::iter::once(&manifest_path).chain(paths) {
+            match asset_server.get_load_state(path.as_str()) {
+                LoadState::NotLoaded | LoadState::Unloaded => continue,
+                LoadState::Loading => {
+                    current.get_or_insert_with(|| path.clone());
+                }
+                LoadState::Loaded | LoadState::Failed => loaded += 1,
+            }
+            total += 1;
+        }
+
+        *progress = LoadingProgress {
+            loaded,
+            total,
+            current,
+        };
+    }
+
+    /// Every asset is loaded and the screen has been up long enough
+    fn finished(progress: Res<LoadingProgress>, state_time: Res<StateTime<AppState>>) -> bool {
+        progress.is_done() && state_time.time >= MIN_LOADING_TIME
+    }
 }
 
+/// Problems found in the manifest at startup, shown on the loading screen which would hang
+#[derive(Debug, Default, Resource)]
+struct ManifestProblems(usize);
+
 pub(crate) struct AssetPlugin;
 
 impl Plugin for AssetPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
-            LoadingState::new(AppState::SplashAssetLoading)
-                .continue_to_state(AppState::Splash)
-                .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![
-                    "dynamic_asset.assets",
-                ])
-                .with_collection::<FontAssets>(),
-        )
-        .add_loading_state(
-            LoadingState::new(AppState::MainMenuAssetLoading)
-                .continue_to_state(AppState::MainMenu)
-                .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![
-                    "dynamic_asset.assets",
-                ])
-                .with_collection::<FontAssets>()
-                .with_collection::<MainMenuAssets>(),
-        )
-        .add_loading_state(
-            LoadingState::new(AppState::InGameAssetLoading)
-                .continue_to_state(AppState::InGame)
-                .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![
-                    "dynamic_asset.assets",
-                ])
-                .with_collection::<FontAssets>()
-                .with_collection::<ImageAssets>()
-                .with_collection::<AudioAssets>(),
-        )
-        .add_enter_system(AppState::MainMenuAssetLoading, loading_screen)
-        .add_enter_system(AppState::InGameAssetLoading, loading_screen);
+        app.init_resource::<ManifestProblems>()
+            .add_startup_system(check_manifest);
+
+        // the loading states don't move on by themselves, `continue_to` waits for the minimum
+        // display time as well
+        app.init_resource::<LoadingProgress>()
+            .add_loading_state(
+                LoadingState::new(AppState::SplashAssetLoading)
+                    .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![MANIFEST])
+                    .with_collection::<FontAssets>(),
+            )
+            .add_loading_state(
+                LoadingState::new(AppState::MainMenuAssetLoading)
+                    .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![MANIFEST])
+                    .with_collection::<FontAssets>()
+                    .with_collection::<MainMenuAssets>()
+                    .with_collection::<SfxAssets>(),
+            )
+            .add_loading_state(
+                LoadingState::new(AppState::InGameAssetLoading)
+                    .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![MANIFEST])
+                    .with_collection::<FontAssets>()
+                    .with_collection::<AnimationAssets>()
+                    .with_collection::<AudioAssets>()
+                    .with_collection::<SfxAssets>(),
+            )
+            .add_system(
+                continue_to(AppState::Splash)
+                    .run_in_state(AppState::SplashAssetLoading)
+                    .run_if(LoadingProgress::finished)
+                    .run_if_resource_exists::<FontAssets>(),
+            )
+            .add_system(
+                continue_to(AppState::MainMenu)
+                    .run_in_state(AppState::MainMenuAssetLoading)
+                    .run_if(LoadingProgress::finished)
+                    .run_if_resource_exists::<FontAssets>()
+                    .run_if_resource_exists::<MainMenuAssets>()
+                    .run_if_resource_exists::<SfxAssets>(),
+            )
+            .add_system(
+                continue_to(AppState::InGame)
+                    .run_in_state(AppState::InGameAssetLoading)
+                    .run_if(LoadingProgress::finished)
+                    .run_if_resource_exists::<FontAssets>()
+                    .run_if_resource_exists::<AnimationAssets>()
+                    .run_if_resource_exists::<AudioAssets>()
+                    .run_if_resource_exists::<SfxAssets>(),
+            );
+
+        for state in [
+            AppState::SplashAssetLoading,
+            AppState::MainMenuAssetLoading,
+            AppState::InGameAssetLoading,
+        ] {
+            app.add_enter_system(state, loading_screen).add_system_set(
+                ConditionSet::new()
+                    .run_in_state(state)
+                    .with_system(LoadingProgress::update)
+                    .with_system(update_loading_screen)
+                    .into(),
+            );
+        }
     }
 }
 
-fn loading_screen(mut cmd: Commands, asset_server: Res<AssetServer>) {
+/// Report the problems of the manifest in the folder the asset server reads from
+///
+/// The game may be embedded, so it doesn't stop, `check-assets` fails on them instead.
+fn check_manifest(asset_server: Res<AssetServer>, mut problems: ResMut<ManifestProblems>) {
+    let asset_dir = asset_server
+        .asset_io()
+        .downcast_ref::<FileAssetIo>()
+        .map_or_else(manifest::asset_dir, |asset_io| asset_io.root_path().clone());
+    let report = manifest::check(&asset_dir);
+
+    for key in &report.unused {
+        warn!("`{key}` of {MANIFEST} isn't used by any collection or level");
+    }
+
+    for err in &report.errors {
+        error!("{err}");
+    }
+
+    problems.0 = report.errors.len();
+}
+
+fn continue_to(state: AppState) -> impl Fn(Commands) {
+    move |mut cmd: Commands| cmd.insert_resource(NextState(state))
+}
+
+#[derive(Component)]
+struct LoadingBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+/// Path of the asset being loaded
+#[derive(Component)]
+struct LoadingLabel;
+
+fn loading_screen(mut cmd: Commands, font_assets: Option<Res<FontAssets>>) {
     cmd.spawn(Camera2dBundle::default());
+    cmd.insert_resource(LoadingProgress::default());
 
-    let font = asset_server.load("fonts/monogram.ttf");
+    // the first loading state of a launch loads the font as well, `update_loading_screen` sets it
+    // once it's there
+    let font = font_assets
+        .map(|font_assets| font_assets.monogram.clone())
+        .unwrap_or_default();
 
     cmd.spawn((
         Name::new("Loading"),
+        LoadingText,
         Text2dBundle {
             text: Text::from_section(
                 "Loading".to_owned(),
                 TextStyle {
-                    font,
+                    font: font.clone(),
                     font_size: 68.0,
                     color: Color::WHITE,
                 },
@@ -90,23 +279,86 @@ fn loading_screen(mut cmd: Commands, asset_server: Res<AssetServer>) {
         },
     ));
 
-    cmd.spawn(SpriteBundle {
-        sprite: Sprite {
-            custom_size: Vec2::new(160., 12.).into(),
-            color: Color::BLACK,
+    cmd.spawn((
+        Name::new("Loading Label"),
+        LoadingText,
+        LoadingLabel,
+        Text2dBundle {
+            text: Text::from_section(
+                String::new(),
+                TextStyle {
+                    font,
+                    font_size: 24.0,
+                    color: Color::GRAY,
+                },
+            )
+            .with_alignment(TextAlignment {
+                horizontal: HorizontalAlign::Center,
+                vertical: VerticalAlign::Center,
+            }),
+            transform: Transform::from_xyz(0., -100., 0.),
+            ..default()
+        },
+    ));
+
+    cmd.spawn((
+        Name::new("Loading Bar Background"),
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: BAR_SIZE.into(),
+                color: Color::BLACK,
+                ..default()
+            },
+            transform: Transform::from_xyz(0., -70., 0.),
             ..default()
         },
-        ..default()
-    })
-    .insert(Transform::from_xyz(0., -70., 0.));
-
-    cmd.spawn(SpriteBundle {
-        sprite: Sprite {
-            custom_size: Vec2::new(160., 12.).into(),
-            color: Color::WHITE,
+    ));
+
+    // grows from its left edge
+    cmd.spawn((
+        Name::new("Loading Bar"),
+        LoadingBar,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: BAR_SIZE.into(),
+                color: Color::WHITE,
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(-BAR_SIZE.x / 2., -70., 1.)
+                .with_scale(Vec3::new(0., 1., 1.)),
             ..default()
         },
-        ..default()
-    })
-    .insert(Transform::from_xyz(0., -70., 0.).with_scale(Vec3::new(0., 1., 0.)));
+    ));
+}
+
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    problems: Res<ManifestProblems>,
+    font_assets: Option<Res<FontAssets>>,
+    mut bars: Query<&mut Transform, With<LoadingBar>>,
+    mut texts: Query<(&mut Text, Option<&LoadingLabel>), With<LoadingText>>,
+) {
+    for mut transform in bars.iter_mut() {
+        transform.scale.x = progress.share();
+    }
+
+    for (mut text, label) in texts.iter_mut() {
+        if let Some(font_assets) = &font_assets {
+            if text.sections[0].style.font != font_assets.monogram {
+                text.sections[0].style.font = font_assets.monogram.clone();
+            }
+        }
+
+        if label.is_some() {
+            let current = if problems.0 > 0 {
+                format!("{} problem(s) with {MANIFEST}, see the log", problems.0)
+            } else {
+                progress.current.clone().unwrap_or_default()
+            };
+            if text.sections[0].value != current {
+                text.sections[0].value = current;
+            }
+        }
+    }
 }
@@ -0,0 +1,126 @@
+//! Checks of `dynamic_asset.assets` against the keys of the asset collections
+//!
+//! A key missing from the manifest, or a file missing from the asset folder, would keep a loading
+//! state waiting forever, so the game reports them instead. Keys which only levels ask for, in
+//! their `Music` or `Assets` fields, start with `level.` and don't need a collection.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::asset::FileAssetIo;
+use bevy_asset_loader::prelude::{StandardDynamicAsset, StandardDynamicAssetCollection};
+use thiserror::Error;
+
+use super::{COLLECTIONS, MANIFEST};
+
+#[derive(Debug, Error)]
+pub(crate) enum ManifestError {
+    #[error("cannot read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("cannot parse {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("`{key}` of {collection} is missing from {MANIFEST}")]
+    MissingKey {
+        collection: &'static str,
+        key: &'static str,
+    },
+    #[error("`{key}` points to {}, which doesn't exist", path.display())]
+    MissingFile { key: String, path: PathBuf },
+}
+
+/// start of the keys which levels ask for
+pub(crate) const LEVEL_KEY_PREFIX: &str = "level.";
+
+#[derive(Debug, Default)]
+pub(crate) struct Report {
+    pub(crate) errors: Vec<ManifestError>,
+    /// keys which neither a collection nor a level uses
+    pub(crate) unused: Vec<String>,
+}
+
+/// The asset folder bevy loads from by default
+pub(crate) fn asset_dir() -> PathBuf {
+    FileAssetIo::get_base_path().join("assets")
+}
+
+pub(crate) fn check(asset_dir: &Path) -> Report {
+    let mut report = Report::default();
+
+    let manifest = match read(&asset_dir.join(MANIFEST)) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            report.errors.push(err);
+            return report;
+        }
+    };
+
+    for &(collection, keys) in COLLECTIONS {
+        for &key in keys {
+            if !manifest.0.contains_key(key) {
+                report
+                    .errors
+                    .push(ManifestError::MissingKey { collection, key });
+            }
+        }
+    }
+
+    let mut entries = manifest.0.into_iter().collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (key, asset) in entries {
+        if let StandardDynamicAsset::File { path } = asset {
+            let path = asset_dir.join(path);
+            if !path.is_file() {
+                report.errors.push(ManifestError::MissingFile {
+                    key: key.clone(),
+                    path,
+                });
+            }
+        }
+
+        if !key.starts_with(LEVEL_KEY_PREFIX)
+            && !COLLECTIONS
+                .iter()
+                .any(|(_, keys)| keys.contains(&key.as_str()))
+        {
+            report.unused.push(key);
+        }
+    }
+
+    report
+}
+
+fn read(path: &Path) -> Result<StandardDynamicAssetCollection, ManifestError> {
+    let content = fs::read_to_string(path).map_err(|source| ManifestError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    ron::from_str(&content).map_err(|err| ManifestError::Parse {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_dir;
+
+    #[test]
+    fn level_keys_are_not_unused() {
+        let dir = test_dir("manifest-level-keys");
+        fs::write(
+            dir.join(MANIFEST),
+            r#"({
+                "level.music.forest": File (path: "audio/ThemeForest.mp3"),
+                "music.forest": File (path: "audio/ThemeForest.mp3"),
+            })"#,
+        )
+        .unwrap();
+
+        let report = check(&dir);
+        assert_eq!(report.unused, ["music.forest"]);
+    }
+}
@@ -7,11 +7,16 @@ use bevy_kira_audio::{
 
 pub(crate) type AudioSource = bevy_kira_audio::AudioSource;
 
+pub(crate) mod music;
+pub(crate) mod sfx;
+
 use crate::{
-    asset::{AudioAssets, MainMenuAssets},
-    state::{AppLooplessStateExt, AppState, CurrentState, NextState},
-    ui::menu::GameConfig,
+    cli::LaunchOptions,
+    state::{AppLooplessStateExt, AppState, CurrentState, IntoConditionalSystem, NextState},
+    ui::{audio::AudioCategory, menu::GameConfig},
 };
+use music::{LevelMusicPlugin, MusicTrack};
+use sfx::PlaySfx;
 
 trait Channel = Sync + Send + Resource;
 
@@ -71,15 +76,29 @@ impl Plugin for AudioPlugin {
             .add_loopless_state(AudioState::default())
             .insert_resource(ChannelState::<MusicChannel>::default())
             .insert_resource(ChannelState::<SoundChannel>::default())
+            .init_resource::<MusicTrack>()
+            .add_plugin(LevelMusicPlugin)
             .add_audio_channel::<MusicChannel>()
             .add_audio_channel::<SoundChannel>()
-            .add_enter_system(AppState::MainMenu, update_main_menu_audio_state)
+            .add_event::<PlaySfx>();
+
+        // the kira plugin still loads the audio assets, nothing is played
+        if app
+            .world
+            .get_resource::<LaunchOptions>()
+            .map_or(false, |launch_options| launch_options.no_audio)
+        {
+            return;
+        }
+
+        app.add_enter_system(AppState::MainMenu, update_main_menu_audio_state)
             .add_enter_system(AppState::InGame, update_in_game_audio_state)
-            .add_enter_system(AudioState::MainMenu, play_menu_music)
-            .add_enter_system(AudioState::InGame, play_game_music)
+            .add_enter_system(AudioState::MainMenu, music::play_menu_music)
+            .add_system(music::play_game_music.run_in_state(AudioState::InGame))
             .add_system_set(setup_controls::<MusicChannel>())
             .add_system_set(setup_controls::<SoundChannel>())
-            .add_system(update_volumes);
+            .add_system(update_volumes)
+            .add_system(sfx::play_sfx);
     }
 }
 
@@ -110,40 +129,12 @@ fn update_volumes(
 ) {
     if let Some(game_config) = game_config {
         if game_config.is_changed() {
-            music_channel.set_volume(*game_config.audio.music_volume);
-            sound_channel.set_volume(*game_config.audio.sound_volume);
+            music_channel.set_volume(game_config.audio.output_volume(AudioCategory::Music));
+            sound_channel.set_volume(game_config.audio.output_volume(AudioCategory::Sound));
         }
     }
 }
 
-fn play_menu_music(
-    mut channel_state: ResMut<ChannelState<MusicChannel>>,
-    main_menu_assets: Res<MainMenuAssets>,
-    audio: Res<AudioChannel<MusicChannel>>,
-    game_config: Res<GameConfig>,
-) {
-    audio.stop();
-    audio.set_volume(*game_config.audio.music_volume);
-    channel_state.reset();
-    channel_state.handle = Some(main_menu_assets.bgm.clone());
-    channel_state.stopped = true;
-    channel_state.looped = true;
-}
-
-fn play_game_music(
-    mut channel_state: ResMut<ChannelState<MusicChannel>>,
-    audio_assets: Res<AudioAssets>,
-    audio: Res<AudioChannel<MusicChannel>>,
-    game_config: Res<GameConfig>,
-) {
-    audio.stop();
-    audio.set_volume(*game_config.audio.music_volume);
-    channel_state.reset();
-    channel_state.handle = Some(audio_assets.bgm.clone());
-    channel_state.stopped = true;
-    channel_state.looped = true;
-}
-
 fn play_channel<T: Channel>(
     mut channel_state: ResMut<ChannelState<T>>,
     audio: Res<AudioChannel<T>>,
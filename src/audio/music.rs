@@ -0,0 +1,228 @@
+//! Music of the menus and of the levels
+//!
+//! A level names its track in its `Music` field, a key of `dynamic_asset.assets`, levels without
+//! one play `AudioAssets::bgm`. Listing the key in the `Assets` field of the level too loads the
+//! track along with the level, before the player walks in from a neighbour.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::StandardDynamicAssetCollection;
+use bevy_kira_audio::{AudioChannel, AudioControl, AudioTween};
+
+use super::{AudioSource, AudioState, MusicChannel};
+use crate::{
+    asset::{AudioAssets, MainMenuAssets},
+    state::{AppLooplessStateExt, AppState, CurrentState, IntoConditionalSystem},
+    tilemap::{
+        ldtk, level_assets::manifest_path, selected_level, FieldValue, LdtkAsset, LevelSelection,
+    },
+};
+
+const MUSIC_FIELD: &str = "Music";
+
+/// seconds the previous track takes to fade out while the next one fades in
+const CROSSFADE_TIME: f32 = 1.5;
+
+/// The track playing on `MusicChannel`
+#[derive(Debug, Default, Resource)]
+pub(crate) struct MusicTrack(Option<Handle<AudioSource>>);
+
+impl MusicTrack {
+    /// Fade out the playing track and fade in `track`, the same track keeps playing
+    fn crossfade_to(&mut self, track: Handle<AudioSource>, audio: &AudioChannel<MusicChannel>) {
+        if self.0.as_ref() == Some(&track) {
+            return;
+        }
+
+        let tween = AudioTween::linear(Duration::from_secs_f32(CROSSFADE_TIME));
+        audio.stop().fade_out(tween.clone());
+        audio.play(track.clone()).looped().fade_in(tween);
+
+        self.0 = Some(track);
+    }
+}
+
+pub(crate) fn play_menu_music(
+    mut music_track: ResMut<MusicTrack>,
+    main_menu_assets: Res<MainMenuAssets>,
+    audio: Res<AudioChannel<MusicChannel>>,
+) {
+    music_track.crossfade_to(main_menu_assets.bgm.clone(), &audio);
+}
+
+/// Crossfades to the track of the level, runs while `AudioState::InGame` is current
+pub(crate) fn play_game_music(
+    mut music_track: ResMut<MusicTrack>,
+    level_music: Res<LevelMusic>,
+    audio_state: Res<CurrentState<AudioState>>,
+    audio: Res<AudioChannel<MusicChannel>>,
+) {
+    if !level_music.is_changed() && !audio_state.is_changed() {
+        return;
+    }
+
+    if let Some(track) = &level_music.0 {
+        music_track.crossfade_to(track.clone(), &audio);
+    }
+}
+
+/// The track of the level the player is in
+#[derive(Debug, Default, Resource)]
+pub(crate) struct LevelMusic(pub(crate) Option<Handle<AudioSource>>);
+
+/// Chooses the track of the selected level, without playing it
+///
+/// It doesn't need an audio device, `AudioPlugin` adds it before checking for one and headless
+/// apps add it on their own.
+pub(crate) struct LevelMusicPlugin;
+
+impl Plugin for LevelMusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelMusic>()
+            .add_system(select_level_music.run_in_state(AppState::InGame))
+            .add_exit_system(AppState::InGame, clear_level_music);
+    }
+}
+
+/// Follows `LevelSelection`, which changes when the player walks into another level
+fn select_level_music(
+    mut level_music: ResMut<LevelMusic>,
+    mut ldtk_events: EventReader<AssetEvent<LdtkAsset>>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    manifests: Res<Assets<StandardDynamicAssetCollection>>,
+    audio_assets: Res<AudioAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    // the project loads after the state is entered and may be edited while playing
+    let ldtk_changed = ldtk_events.iter().count() > 0;
+    if level_music.0.is_some() && !ldtk_changed && !level_selection.is_changed() {
+        return;
+    }
+
+    let path = selected_level(&level_selection, &ldtk_assets).and_then(|level| {
+        let key = music_key(level)?;
+        let path = manifest_path(key, &manifests);
+        if path.is_none() {
+            warn!("level {} asks for unknown music {key}", level.identifier);
+        }
+        path
+    });
+
+    let track = match path {
+        Some(path) => asset_server.load(path),
+        None => audio_assets.bgm.clone(),
+    };
+
+    // the same track keeps playing
+    if level_music.0.as_ref() != Some(&track) {
+        level_music.0 = Some(track);
+    }
+}
+
+fn clear_level_music(mut level_music: ResMut<LevelMusic>) {
+    level_music.0 = None;
+}
+
+fn music_key(level: &ldtk::Level) -> Option<&str> {
+    level
+        .field_instances
+        .iter()
+        .find(|field| field.identifier == MUSIC_FIELD)
+        .and_then(|field| match &field.value {
+            FieldValue::String(key) => key.as_deref(),
+            _ => {
+                warn!("{MUSIC_FIELD} of level {} isn't a string", level.identifier);
+                None
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::ControlAction, testing::TestApp, tilemap::ldtk::FieldInstance};
+
+    fn music_field(key: &str) -> FieldInstance {
+        FieldInstance {
+            identifier: MUSIC_FIELD.to_string(),
+            tile: None,
+            field_instance_type: "String".to_string(),
+            value: FieldValue::String(Some(key.to_string())),
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    fn track(app: &TestApp, path: &str) -> Handle<AudioSource> {
+        app.world.resource::<AssetServer>().get_handle(path)
+    }
+
+    /// Two levels side by side with the player at the end of the first one, returns the player
+    fn walk_between(app: &mut TestApp, first: Option<&str>, second: Option<&str>) -> Entity {
+        let fields = |key: Option<&str>| key.map(music_field).into_iter().collect();
+
+        app.spawn_level(0, Vec2::new(0., 0.), Vec2::new(320., 160.), fields(first));
+        app.spawn_level(
+            1,
+            Vec2::new(320., 0.),
+            Vec2::new(320., 160.),
+            fields(second),
+        );
+        app.spawn_ground(Vec2::new(320., 8.), Vec2::new(320., 8.));
+        let player = app.spawn_player(Vec2::new(280., 32.), 3);
+
+        // land on the ground of the first level
+        app.step_seconds(1.);
+
+        player
+    }
+
+    fn walk_over(app: &mut TestApp, player: Entity) {
+        app.press(player, ControlAction::Right);
+        app.step_seconds(1.);
+        assert!(app.translation(player).x > 320., "player didn't walk over");
+    }
+
+    #[test]
+    fn walking_into_the_next_level_switches_the_track() {
+        let mut app = TestApp::new();
+        let _manifest = app.add_manifest(&[
+            ("level.music.forest", "audio/ThemeForest.mp3"),
+            ("level.music.town", "audio/TownTheme.mp3"),
+        ]);
+        let player = walk_between(
+            &mut app,
+            Some("level.music.forest"),
+            Some("level.music.town"),
+        );
+
+        assert_eq!(
+            app.world.resource::<LevelMusic>().0,
+            Some(track(&app, "audio/ThemeForest.mp3"))
+        );
+
+        walk_over(&mut app, player);
+        assert_eq!(
+            app.world.resource::<LevelMusic>().0,
+            Some(track(&app, "audio/TownTheme.mp3"))
+        );
+    }
+
+    #[test]
+    fn level_without_music_plays_the_default_track() {
+        let mut app = TestApp::new();
+        let _manifest = app.add_manifest(&[("level.music.town", "audio/TownTheme.mp3")]);
+        let bgm = track(&app, "audio/ThemeForest.mp3");
+        app.insert_resource(AudioAssets { bgm: bgm.clone() });
+        let player = walk_between(&mut app, Some("level.music.town"), None);
+
+        assert_eq!(
+            app.world.resource::<LevelMusic>().0,
+            Some(track(&app, "audio/TownTheme.mp3"))
+        );
+
+        walk_over(&mut app, player);
+        assert_eq!(app.world.resource::<LevelMusic>().0, Some(bgm));
+    }
+}
@@ -0,0 +1,94 @@
+//! Sound effects played on `SoundChannel`
+//!
+//! Gameplay and the menus send `PlaySfx`, the sounds are loaded from the `sfx.*` keys of the
+//! asset manifest into `SfxAssets`.
+use bevy::prelude::*;
+use bevy_kira_audio::{AudioChannel, AudioControl, AudioInstance, PlaybackState};
+
+use super::{AudioSource, SoundChannel};
+use crate::{
+    asset::SfxAssets,
+    ui::{audio::AudioCategory, menu::GameConfig},
+};
+
+/// sounds playing at once, the ones asked for past it are dropped
+const MAX_VOICES: usize = 8;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum Sfx {
+    Jump,
+    Dash,
+    Attack,
+    Hit,
+    MobDeath,
+    CheckPoint,
+    Click,
+}
+
+impl Sfx {
+    /// How far the playback rate and the volume may stray from 1, picked anew for every play
+    fn variation(self) -> (f64, f64) {
+        match self {
+            Self::Click => (0., 0.),
+            Self::Jump | Self::Dash | Self::Attack => (0.08, 0.1),
+            Self::Hit | Self::MobDeath | Self::CheckPoint => (0.05, 0.1),
+        }
+    }
+}
+
+impl SfxAssets {
+    fn get(&self, sfx: Sfx) -> &Handle<AudioSource> {
+        match sfx {
+            Sfx::Jump => &self.jump,
+            Sfx::Dash => &self.dash,
+            Sfx::Attack => &self.attack,
+            Sfx::Hit => &self.hit,
+            Sfx::MobDeath => &self.mob_death,
+            Sfx::CheckPoint => &self.check_point,
+            Sfx::Click => &self.click,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PlaySfx(pub(crate) Sfx);
+
+/// a factor within `1 ± variation`
+fn vary(variation: f64) -> f64 {
+    1. + (fastrand::f64() * 2. - 1.) * variation
+}
+
+pub(crate) fn play_sfx(
+    mut play_events: EventReader<PlaySfx>,
+    sfx_assets: Option<Res<SfxAssets>>,
+    audio: Res<AudioChannel<SoundChannel>>,
+    game_config: Res<GameConfig>,
+    mut voices: Local<Vec<Handle<AudioInstance>>>,
+) {
+    voices.retain(|voice| !matches!(audio.state(voice), PlaybackState::Stopped));
+
+    // the splash screen plays before the sounds are loaded
+    let sfx_assets = match sfx_assets {
+        Some(sfx_assets) => sfx_assets,
+        None => return,
+    };
+
+    for PlaySfx(sfx) in play_events.iter() {
+        if voices.len() >= MAX_VOICES {
+            debug!("too many sounds playing, dropping {sfx:?}");
+            continue;
+        }
+
+        let (pitch, volume) = sfx.variation();
+        // the volume of a sound replaces the one of the channel
+        let volume = vary(volume) * game_config.audio.output_volume(AudioCategory::Sound);
+
+        let voice = audio
+            .play(sfx_assets.get(*sfx).clone())
+            .with_playback_rate(vary(pitch))
+            .with_volume(volume)
+            .handle();
+
+        voices.push(voice);
+    }
+}
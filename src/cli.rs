@@ -0,0 +1,190 @@
+//! Command line options, and commands run instead of launching the game
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+#[cfg(feature = "debug")]
+use crate::save::export::{convert, FileKind};
+use crate::{asset::manifest, state::AppState, tilemap::LevelSelection};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub(crate) struct Cli {
+    /// Keep saves and config in this directory instead of the user data directories, also set
+    /// with `RECKONING_DATA_DIR`
+    #[arg(long, global = true)]
+    pub(crate) data_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub(crate) launch: LaunchOptions,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Shortcuts into the game, mostly useful while developing it
+///
+/// ```sh
+/// cargo run -- --slot 1 --level Level_3 --spawn 120,64
+/// ```
+#[derive(Args, Clone, Debug, Default, Resource)]
+pub(crate) struct LaunchOptions {
+    /// Screen to start on, defaults to `in-game` when a slot, level or spawn position is given
+    #[arg(long, value_enum)]
+    pub(crate) state: Option<StartState>,
+
+    /// LDtk level to start in, by uid or identifier
+    #[arg(long, value_parser = parse_level)]
+    pub(crate) level: Option<LevelSelection>,
+
+    /// Save slot to play
+    #[arg(long)]
+    pub(crate) slot: Option<usize>,
+
+    /// Position of the player in the level it starts in, in pixels from the bottom left corner of
+    /// that level, as `X,Y`
+    #[arg(long, value_parser = parse_vec2, allow_hyphen_values = true)]
+    pub(crate) spawn: Option<Vec2>,
+
+    /// Size of the window, as `WIDTHxHEIGHT`
+    #[arg(long, value_parser = parse_window_size)]
+    pub(crate) window_size: Option<Vec2>,
+
+    /// Don't play any music or sound
+    #[arg(long)]
+    pub(crate) no_audio: bool,
+}
+
+impl LaunchOptions {
+    /// `None` when no option asks for a particular state
+    pub(crate) fn start_state(&self) -> Option<AppState> {
+        self.state
+            .or_else(|| {
+                (self.slot.is_some() || self.level.is_some() || self.spawn.is_some())
+                    .then_some(StartState::InGame)
+            })
+            .map(AppState::from)
+    }
+}
+
+/// States a launch can start in, the ones which need assets start with loading them
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum StartState {
+    Splash,
+    MainMenu,
+    InGame,
+}
+
+impl From<StartState> for AppState {
+    fn from(state: StartState) -> Self {
+        match state {
+            StartState::Splash => Self::SplashAssetLoading,
+            StartState::MainMenu => Self::MainMenuAssetLoading,
+            StartState::InGame => Self::InGameAssetLoading,
+        }
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelSelection, String> {
+    Ok(match level.parse() {
+        Ok(uid) => LevelSelection::Uid(uid),
+        Err(_) => LevelSelection::Identifier(level.to_string()),
+    })
+}
+
+fn parse_pair(value: &str, separator: char) -> Result<Vec2, String> {
+    let (x, y) = value
+        .split_once(separator)
+        .ok_or_else(|| format!("expected two numbers separated by `{separator}`"))?;
+
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<f32>()
+            .map_err(|err| format!("{n:?}: {err}"))
+    };
+
+    Ok(Vec2::new(parse(x)?, parse(y)?))
+}
+
+fn parse_vec2(value: &str) -> Result<Vec2, String> {
+    parse_pair(value, ',')
+}
+
+fn parse_window_size(value: &str) -> Result<Vec2, String> {
+    let size = parse_pair(value, 'x')?;
+
+    if size.x <= 0. || size.y <= 0. {
+        return Err("the window size must be positive".to_string());
+    }
+
+    Ok(size)
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check `dynamic_asset.assets` against the asset collections of the game
+    CheckAssets {
+        /// defaults to the folder the game loads its assets from
+        #[arg(long)]
+        assets: Option<PathBuf>,
+    },
+    /// Convert a save or config file between the binary format and RON or JSON
+    ///
+    /// The formats are picked from the file extensions, `.ron`, `.json` or binary for anything
+    /// else.
+    #[cfg(feature = "debug")]
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// guessed from the input file name when omitted
+        #[arg(long, value_enum)]
+        kind: Option<FileKind>,
+    },
+}
+
+impl Cli {
+    /// Run the command given on the command line, returns false when the game should be launched
+    pub(crate) fn run_command(&self) -> bool {
+        let command = match &self.command {
+            Some(command) => command,
+            None => return false,
+        };
+
+        match command {
+            Command::CheckAssets { assets } => {
+                let asset_dir = assets.clone().unwrap_or_else(manifest::asset_dir);
+                let report = manifest::check(&asset_dir);
+
+                for key in &report.unused {
+                    println!("warning: `{key}` isn't used by any collection or level");
+                }
+
+                for err in &report.errors {
+                    eprintln!("error: {err}");
+                }
+
+                if !report.errors.is_empty() {
+                    std::process::exit(1);
+                }
+
+                println!("{} is fine", asset_dir.display());
+            }
+            #[cfg(feature = "debug")]
+            Command::Convert {
+                input,
+                output,
+                kind,
+            } => {
+                if let Err(err) = convert(input, output, *kind) {
+                    eprintln!("error: {err:#}");
+                    std::process::exit(1);
+                }
+
+                println!("converted {} to {}", input.display(), output.display());
+            }
+        }
+
+        true
+    }
+}
@@ -19,6 +19,10 @@ pub(crate) enum ControlAction {
     // Actions
     Jump,
     Attack,
+
+    // Saves
+    QuickSave,
+    QuickLoad,
 }
 
 pub(crate) type ControlActionState = ActionState<ControlAction>;
@@ -33,8 +37,46 @@ impl ControlAction {
             (KeyCode::W, Self::Up),
             (KeyCode::S, Self::Down),
             (KeyCode::J, Self::Attack),
+            (KeyCode::F5, Self::QuickSave),
+            (KeyCode::F9, Self::QuickLoad),
         ])
     }
+
+    /// Input map of a saved config, with the default inputs of actions it has no input for.
+    ///
+    /// Actions added after the config was written get their default inputs this way, unless those
+    /// are bound to another action already.
+    pub(crate) fn with_default_inputs(input_map: &ControlInputMap) -> ControlInputMap {
+        let defaults = Self::get_input_map();
+        let mut merged = ControlInputMap::default();
+
+        let bound_inputs: Vec<UserInput> = input_map
+            .iter()
+            .flat_map(|(inputs, _)| inputs.iter().cloned())
+            .collect();
+
+        for action in Self::variants() {
+            match input_map
+                .iter()
+                .find(|(inputs, bound_action)| *bound_action == action && !inputs.is_empty())
+            {
+                Some((inputs, _)) => {
+                    for input in inputs.iter() {
+                        merged.insert(input.clone(), action);
+                    }
+                }
+                None => {
+                    for input in defaults.get(action).iter() {
+                        if !bound_inputs.contains(input) {
+                            merged.insert(input.clone(), action);
+                        }
+                    }
+                }
+            }
+        }
+
+        merged
+    }
 }
 
 #[derive(Bundle, Clone)]
@@ -120,7 +162,7 @@ fn update_control_input_map(
 ) {
     if let Some(game_config) = game_config {
         if game_config.is_changed() {
-            *control_input_map = game_config.control.input_map.clone();
+            *control_input_map = ControlAction::with_default_inputs(&game_config.control.input_map);
         }
     }
 }
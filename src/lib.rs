@@ -2,9 +2,11 @@
 #![feature(path_file_prefix)]
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
 
+mod animation;
 mod asset;
 mod audio;
 mod camera;
+mod cli;
 mod input;
 mod npc;
 mod physics;
@@ -12,30 +14,126 @@ mod player;
 mod save;
 mod splash_screen;
 mod state;
+#[cfg(test)]
+mod testing;
 mod tilemap;
 mod ui;
 mod weapon;
 
-use bevy::prelude::*;
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use clap::Parser;
+
+use cli::Cli;
+
+pub use bevy_ecs_ldtk::LevelSelection;
+pub use npc::Enemy;
+pub use player::{Health, Player};
+pub use tilemap::check_point::LastCheckPoint;
+pub use weapon::Projectile;
+
+/// The whole game, on top of bevy's `DefaultPlugins`
+///
+/// Tools and prototypes can embed the platformer core by turning off the menus and pointing it at
+/// their own LDtk project.
+#[derive(Clone, Debug)]
+pub struct ReckoningPlugins {
+    /// LDtk project to play, relative to the asset folder
+    pub ldtk_file: String,
+    /// level a new game starts in
+    pub start_level: LevelSelection,
+    /// main menu, save menu, pause menu and options, without them the game starts right away
+    pub menus: bool,
+    /// splash screen before the main menu, only shown with the menus
+    pub splash: bool,
+    /// pixels per meter of the physics
+    pub physics_scale: f32,
+}
+
+impl Default for ReckoningPlugins {
+    fn default() -> Self {
+        let world = tilemap::WorldConfig::default();
+
+        Self {
+            ldtk_file: world.ldtk_file,
+            start_level: world.start_level,
+            menus: true,
+            splash: true,
+            physics_scale: physics::PhysicsPlugin::default().scale,
+        }
+    }
+}
+
+impl PluginGroup for ReckoningPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        let start_state = match (self.menus, self.splash) {
+            (false, _) => state::AppState::InGameAssetLoading,
+            (true, false) => state::AppState::MainMenuAssetLoading,
+            (true, true) => state::AppState::SplashAssetLoading,
+        };
+
+        let mut group = PluginGroupBuilder::start::<Self>()
+            //state should be loaded first as there are a lot of plugins depend on `AppState`
+            .add(state::StatePlugin { start_state })
+            .add(animation::AnimationPlugin)
+            .add(asset::AssetPlugin)
+            .add(audio::AudioPlugin)
+            .add(camera::CameraPlugin)
+            .add(tilemap::TilemapPlugin {
+                world: tilemap::WorldConfig {
+                    ldtk_file: self.ldtk_file,
+                    start_level: self.start_level,
+                },
+            })
+            .add(input::InputPlugin)
+            .add(npc::NpcPlugin)
+            .add(physics::PhysicsPlugin {
+                scale: self.physics_scale,
+            })
+            .add(player::PlayerPlugin)
+            .add(save::SavePlugin)
+            .add(ui::UiPlugin { menus: self.menus })
+            .add(weapon::WeaponPlugin);
+
+        if self.menus && self.splash {
+            group = group.add(splash_screen::SplashScreenPlugin);
+        }
+
+        group
+    }
+}
 
 pub fn run() {
+    let cli = Cli::parse();
+
+    if cli.run_command() {
+        return;
+    }
+
+    let mut window = WindowDescriptor::default();
+    if let Some(size) = cli.launch.window_size {
+        window.width = size.x;
+        window.height = size.y;
+    }
+
+    let data_dirs = save::DataDirs::new(cli.data_dir);
+
     let mut app = App::new();
 
-    app.add_plugins(DefaultPlugins)
-        //state should be loaded first as there are a lot of plugins depend on `AppState`
-        .add_plugin(state::StatePlugin)
-        .add_plugin(asset::AssetPlugin)
-        .add_plugin(audio::AudioPlugin)
-        .add_plugin(camera::CameraPlugin)
-        .add_plugin(tilemap::TilemapPlugin)
-        .add_plugin(input::InputPlugin)
-        .add_plugin(npc::NpcPlugin)
-        .add_plugin(physics::PhysicsPlugin)
-        .add_plugin(player::PlayerPlugin)
-        .add_plugin(save::SavePlugin)
-        .add_plugin(splash_screen::SplashScreenPlugin)
-        .add_plugin(ui::UiPlugin)
-        .add_plugin(weapon::WeaponPlugin);
+    app.insert_resource(data_dirs.clone())
+        .insert_resource(cli.launch)
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window,
+                    ..default()
+                })
+                // levels are respawned when the LDtk project is saved
+                .set(bevy::asset::AssetPlugin {
+                    watch_for_changes: true,
+                    ..default()
+                }),
+        )
+        .add_plugins(ReckoningPlugins::default());
 
     #[cfg(feature = "debug")]
     app.add_plugin(move_vis::MoveVisPlugin);
@@ -43,5 +141,8 @@ pub fn run() {
     #[cfg(feature = "debug")]
     app.add_plugin(bevy_inspector_egui::WorldInspectorPlugin::new());
 
+    // once the log is set up, only the game itself moves what older versions left behind
+    save::migrate_legacy_dirs(&data_dirs);
+
     app.run();
 }
@@ -3,27 +3,42 @@
 use bevy::prelude::*;
 
 use crate::{
-    asset::ImageAssets,
+    animation::{Animation, Hurt},
+    asset::AnimationAssets,
+    audio::sfx::{PlaySfx, Sfx},
     physics::*,
     player::Health,
+    save::LevelStates,
     state::{AppState, ConditionSet},
     tilemap::{
-        ldtk_pixel_coords_to_translation_pivoted, EntityInstance, FieldValue, LayerInstance,
-        LdtkLevel,
+        find_entity_layer, ldtk_pixel_coords_to_translation_pivoted, EntityInstance, FieldValue,
+        LayerInstance, LdtkLevel, LevelIid,
     },
 };
 
 #[derive(Clone, Component, Default)]
-pub(crate) struct Enemy;
+pub struct Enemy;
 
 impl Enemy {
     fn despawn(
         mut cmd: Commands,
-        enemies: Query<(Entity, &Health), (With<Enemy>, Changed<Health>)>,
+        enemies: Query<
+            (Entity, &Health, &EntityInstance, &LevelIid),
+            (With<Enemy>, Changed<Health>),
+        >,
+        mut level_states: ResMut<LevelStates>,
+        mut sfx_events: EventWriter<PlaySfx>,
     ) {
-        for (entity, health) in enemies.iter() {
+        for (entity, health, entity_instance, level_iid) in enemies.iter() {
             if health.current == 0 {
+                level_states
+                    .entry(level_iid.0.clone())
+                    .or_default()
+                    .dead_enemies
+                    .insert(entity_instance.iid.clone());
+
                 cmd.entity(entity).despawn_recursive();
+                sfx_events.send(PlaySfx(Sfx::MobDeath));
             }
         }
     }
@@ -33,39 +48,39 @@ impl Enemy {
         entity_query: Query<(Entity, &Transform, &EntityInstance), Added<EntityInstance>>,
         level_query: Query<&Handle<LdtkLevel>>,
         levels: Res<Assets<LdtkLevel>>,
-        image_assets: Res<ImageAssets>,
+        animation_assets: Res<AnimationAssets>,
+        level_states: Res<LevelStates>,
     ) {
         for (entity, transform, entity_instance) in entity_query.iter() {
             if entity_instance.identifier == *"Mob" {
-                let layer_instance: &LayerInstance = level_query
-                    .iter()
-                    .find_map(|handle| {
-                        levels
-                            .get(handle)
-                            .expect("Level should be loaded by this point")
-                            .level
-                            .layer_instances
-                            .iter()
-                            .flatten()
-                            .find(|layer| layer.identifier == "Mobs")
-                    })
-                    .expect("Mobs layer is not found in Ldtk file");
-
-                cmd.entity(entity).insert(MobBundle {
-                    sprite_bundle: SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::RED,
+                let (level, layer_instance) =
+                    find_entity_layer(entity_instance, &level_query, &levels)
+                        .expect("Mobs layer is not found in Ldtk file");
+
+                // killed in a previous session
+                if level_states.is_enemy_dead(&level.iid, &entity_instance.iid) {
+                    cmd.entity(entity).despawn_recursive();
+                    continue;
+                }
+
+                cmd.entity(entity).insert((
+                    MobBundle {
+                        sprite_sheet_bundle: SpriteSheetBundle {
+                            sprite: TextureAtlasSprite {
+                                color: Color::RED,
+                                ..default()
+                            },
+                            transform: *transform,
                             ..default()
                         },
-                        texture: image_assets.player.clone(),
-                        transform: *transform,
+                        animation: Animation::new(animation_assets.mob.clone()),
+                        collider_bundle: entity_instance.into(),
+                        hp: entity_instance.into(),
+                        patrol: Patrol::new(entity_instance, layer_instance),
                         ..default()
                     },
-                    collider_bundle: entity_instance.into(),
-                    hp: entity_instance.into(),
-                    patrol: Patrol::new(entity_instance, layer_instance),
-                    ..default()
-                });
+                    LevelIid(level.iid.clone()),
+                ));
             }
         }
     }
@@ -113,7 +128,11 @@ impl From<&EntityInstance> for MobPhysicsBundle {
 #[derive(Clone, Default, Bundle)]
 pub(crate) struct MobBundle {
     #[bundle]
-    pub(crate) sprite_bundle: SpriteBundle,
+    pub(crate) sprite_sheet_bundle: SpriteSheetBundle,
+
+    pub(crate) animation: Animation,
+
+    pub(crate) hurt: Hurt,
 
     #[bundle]
     pub(crate) collider_bundle: MobPhysicsBundle,
@@ -9,13 +9,30 @@ use crate::{
 
 // pub const SCALE: f32 = 100.0;
 // pub const SCALE: f32 = 10.0;
-pub(crate) const SCALE: f32 = 1.0;
+const DEFAULT_SCALE: f32 = 1.0;
 
-pub(crate) struct PhysicsPlugin;
+/// Pixels per meter of rapier, set through `PhysicsPlugin`
+#[derive(Clone, Copy, Debug, Resource)]
+pub(crate) struct PhysicsScale(pub(crate) f32);
+
+pub(crate) struct PhysicsPlugin {
+    pub(crate) scale: f32,
+}
+
+impl Default for PhysicsPlugin {
+    fn default() -> Self {
+        Self {
+            scale: DEFAULT_SCALE,
+        }
+    }
+}
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(SCALE))
+        app.insert_resource(PhysicsScale(self.scale))
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+                self.scale,
+            ))
             .add_enter_system(AppState::InGame, setup)
             .add_system_set(
                 ConditionSet::new()
@@ -52,9 +69,14 @@ fn pause_physics_during_load(
 fn setup(
     mut rapier_config: ResMut<RapierConfiguration>,
     mut player_movement_settings: ResMut<PlayerMovementSettings>,
+    physics_scale: Res<PhysicsScale>,
 ) {
     set_gravity(&mut rapier_config, &player_movement_settings);
-    set_jump_power_coefficient(&rapier_config, &mut player_movement_settings);
+    set_jump_power_coefficient(
+        &rapier_config,
+        &mut player_movement_settings,
+        *physics_scale,
+    );
 }
 
 /// what is the gravity that would allow jumping to a given height?
@@ -73,8 +95,9 @@ pub(crate) fn set_gravity(
 pub(crate) fn set_jump_power_coefficient(
     rapier_config: &ResMut<RapierConfiguration>,
     player_movement_settings: &mut PlayerMovementSettings,
+    physics_scale: PhysicsScale,
 ) {
     player_movement_settings.jump_power_coefficient =
         (2.0 * rapier_config.gravity.y.abs() * player_movement_settings.jump_height).sqrt();
-    player_movement_settings.jump_power_coefficient *= 50.0 / SCALE.powf(2.0);
+    player_movement_settings.jump_power_coefficient *= 50.0 / physics_scale.0.powf(2.0);
 }
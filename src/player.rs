@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use move_vis::TrackMovement;
 
 use crate::{
+    animation::{Animation, Hurt},
     input::ControlInputManagerBundle,
     physics::*,
     state::{AppState, ConditionSet, PauseState},
@@ -31,13 +32,13 @@ const DEFAULT_GRAVITY_SCALE: f32 = 5.0;
 
 #[derive(Clone, Component, Copy, Debug, Default, Reflect, Deserialize, Serialize)]
 #[reflect(Component)]
-pub(crate) struct Health {
-    pub(crate) current: u32,
-    pub(crate) max: u32,
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
 }
 
 impl Health {
-    pub(crate) fn new(hp: u32) -> Self {
+    pub fn new(hp: u32) -> Self {
         Self {
             current: hp,
             max: hp,
@@ -70,9 +71,9 @@ impl From<EntityInstance> for Health {
 }
 
 #[derive(Debug)]
-pub(crate) struct DeathEvent(Entity);
+pub(crate) struct DeathEvent(pub(crate) Entity);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub(crate) enum Direction {
     Left = -1,
     Neutral = 0,
@@ -152,7 +153,7 @@ pub(crate) enum JumpStatus {
 }
 
 #[derive(Clone, Component, Debug)]
-pub(crate) struct Player {
+pub struct Player {
     pub(crate) dashing: bool,
     pub(crate) facing_direction: Direction,
     pub(crate) rising: bool,
@@ -195,7 +196,11 @@ pub(crate) struct PlayerPhysicsBundle {
 #[derive(Clone, Bundle, Default)]
 pub(crate) struct PlayerBundle {
     #[bundle]
-    pub(crate) sprite_bundle: SpriteBundle,
+    pub(crate) sprite_sheet_bundle: SpriteSheetBundle,
+
+    pub(crate) animation: Animation,
+
+    pub(crate) hurt: Hurt,
 
     #[bundle]
     input_manager: ControlInputManagerBundle,
@@ -298,3 +303,67 @@ impl Plugin for PlayerPlugin {
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::LaunchOptions, input::ControlAction, testing::TestApp, tilemap::LevelSelection,
+    };
+
+    #[test]
+    fn jump_reaches_jump_height() {
+        let mut app = TestApp::new();
+        app.spawn_ground(Vec2::new(0., 0.), Vec2::new(200., 8.));
+        let player = app.spawn_player(Vec2::new(0., 24.), 3);
+
+        // land on the ground
+        app.step_seconds(1.);
+        let ground_y = app.translation(player).y;
+
+        app.press(player, ControlAction::Jump);
+
+        let mut highest = ground_y;
+        for _ in 0..120 {
+            app.step();
+            highest = highest.max(app.translation(player).y);
+        }
+
+        let jump_height = app.world.resource::<PlayerMovementSettings>().jump_height;
+        assert!(
+            highest - ground_y >= jump_height,
+            "jumped {} pixels, expected at least {jump_height}",
+            highest - ground_y
+        );
+    }
+
+    #[test]
+    fn player_starts_in_the_level_given_on_the_command_line() {
+        let mut app = TestApp::new();
+        app.spawn_level(0, Vec2::new(0., 0.), Vec2::new(320., 160.), Vec::new());
+        app.spawn_level(1, Vec2::new(320., 0.), Vec2::new(320., 160.), Vec::new());
+        app.spawn_ground(Vec2::new(320., 8.), Vec2::new(400., 8.));
+        // as with `--level 1`
+        app.insert_resource(LevelSelection::Uid(1));
+        let player = app.spawn_player(Vec2::new(40., 32.), 3);
+        app.step();
+
+        assert_eq!(app.translation(player).x, 360.);
+        assert_eq!(
+            *app.world.resource::<LevelSelection>(),
+            LevelSelection::Iid("test-level-1".to_string())
+        );
+    }
+
+    #[test]
+    fn spawn_position_is_in_the_selected_level() {
+        let mut app = TestApp::new();
+        app.spawn_level(0, Vec2::new(0., 0.), Vec2::new(320., 160.), Vec::new());
+        app.spawn_level(1, Vec2::new(320., 0.), Vec2::new(320., 160.), Vec::new());
+        app.insert_resource(LevelSelection::Uid(1));
+        app.world.resource_mut::<LaunchOptions>().spawn = Some(Vec2::new(100., 64.));
+        let player = app.spawn_player(Vec2::new(40., 32.), 3);
+
+        assert_eq!(app.translation(player).truncate(), Vec2::new(420., 64.));
+    }
+}
@@ -3,11 +3,16 @@ use std::time::Duration;
 use bevy::prelude::*;
 
 use crate::{
-    asset::ImageAssets,
+    asset::AnimationAssets,
+    audio::sfx::{PlaySfx, Sfx},
+    cli::LaunchOptions,
     input::{ControlAction, ControlActionState, ControlInputManagerBundle, ControlInputMap},
     physics::*,
-    save::CurrentSave,
-    tilemap::{check_point::LastCheckPoint, EntityInstance, LevelSelection, LevelSize},
+    save::{quick::QuickLoaded, CurrentSave},
+    tilemap::{
+        check_point::LastCheckPoint, hot_reload::ReloadedPlayer, is_same_level, selected_level,
+        EntityInstance, LdtkAsset, LdtkLevel, LevelSelection,
+    },
     weapon::{spawn_projectile, WeaponCooldown},
 };
 
@@ -54,6 +59,7 @@ pub(crate) fn dash(
     )>,
     rapier_context: Res<RapierContext>,
     player_movement_settings: Res<PlayerMovementSettings>,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
     for (player_entity, mut velocity, mut gravity_scale, mut player, action_state) in
         query.iter_mut()
@@ -86,6 +92,9 @@ pub(crate) fn dash(
                         time.delta_seconds(),
                     );
 
+                    if !player.dashing {
+                        sfx_events.send(PlaySfx(Sfx::Dash));
+                    }
                     player.dashing = true;
 
                     *gravity_scale = GravityScale(0.0);
@@ -154,6 +163,7 @@ pub(crate) fn jump(
     player_movement_settings: Res<PlayerMovementSettings>,
     rapier_context: Res<RapierContext>,
     rapier_config: Res<RapierConfiguration>,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
     // let jump_impulse = 10000.0; // SCALE = 1.0
     // let jump_impulse = 100.0;  // SCALE = 10.0
@@ -235,6 +245,7 @@ pub(crate) fn jump(
             }
             JumpStatus::InitiateJump => {
                 velocity.linvel += Vec2::Y * player_movement_settings.jump_power_coefficient;
+                sfx_events.send(PlaySfx(Sfx::Jump));
 
                 player.rising = true;
 
@@ -297,21 +308,37 @@ pub(crate) fn run(
 }
 
 pub(crate) fn boundary(
-    level_size: Res<LevelSize>,
+    levels: Query<(&Handle<LdtkLevel>, &GlobalTransform)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
     mut players: Query<(&mut Transform, &EntityInstance), With<Player>>,
 ) {
-    // during startup, there is a few frames that level_size is not initialised
-    if let Some(level_size) = level_size.0 {
-        for (mut transform, entity_instance) in players.iter_mut() {
-            // half width of player is the offset
-            // origin of player is the centre
-            let offset = entity_instance.width as f32 / 2.0;
-
-            if transform.translation.x > level_size.x - offset {
-                transform.translation.x = level_size.x - offset;
-            } else if transform.translation.x <= offset {
-                transform.translation.x = offset;
-            }
+    // the spawned levels are the selected one and its neighbours, the player walks between them
+    let (left, right) = levels
+        .iter()
+        .filter_map(|(level_handle, level_transform)| {
+            let level = &ldtk_levels.get(level_handle)?.level;
+            let left = level_transform.translation().x;
+            Some((left, left + level.px_wid as f32))
+        })
+        .fold(
+            (f32::INFINITY, f32::NEG_INFINITY),
+            |(min, max), (left, right)| (min.min(left), max.max(right)),
+        );
+
+    // during startup, there is a few frames without levels
+    if left > right {
+        return;
+    }
+
+    for (mut transform, entity_instance) in players.iter_mut() {
+        // half width of player is the offset
+        // origin of player is the centre
+        let offset = entity_instance.width as f32 / 2.0;
+
+        if transform.translation.x > right - offset {
+            transform.translation.x = right - offset;
+        } else if transform.translation.x <= left + offset {
+            transform.translation.x = left + offset;
         }
     }
 }
@@ -324,10 +351,12 @@ pub(crate) fn attack(
         &Player,
         &mut WeaponCooldown,
     )>,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
     for (transform, action_state, player, mut cooldown) in players.iter_mut() {
         if action_state.pressed(ControlAction::Attack) && cooldown.finished() {
             spawn_projectile(&mut cmd, &transform.translation, player);
+            sfx_events.send(PlaySfx(Sfx::Attack));
             cooldown.reset();
         }
     }
@@ -359,15 +388,17 @@ pub(crate) fn hp_death(
 pub(crate) fn process_death_event(
     mut death_events: EventReader<DeathEvent>,
     mut players: Query<(&mut Transform, &LastCheckPoint), With<Player>>,
-    level_selection: Res<LevelSelection>,
+    mut level_selection: ResMut<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
 ) {
     for DeathEvent(dead_player) in death_events.iter() {
         if let Ok((mut transform, last_check_point)) = players.get_mut(*dead_player) {
-            match (&last_check_point.level, level_selection.as_ref()) {
-                (LevelSelection::Uid(a), LevelSelection::Uid(b)) if a == b => {
-                    transform.translation = last_check_point.coordinate
-                }
-                _ => (),
+            transform.translation = last_check_point.coordinate;
+
+            // the player may have walked on since touching the check point, levels use world
+            // coordinates so its level only needs to be spawned again
+            if !is_same_level(&last_check_point.level, &level_selection, &ldtk_assets) {
+                *level_selection = last_check_point.level.clone();
             }
         }
     }
@@ -376,24 +407,101 @@ pub(crate) fn process_death_event(
 pub(crate) fn spawn_player(
     mut cmd: Commands,
     entity_query: Query<(Entity, &Transform, &EntityInstance), Added<EntityInstance>>,
-    image_assets: Res<ImageAssets>,
+    animation_assets: Res<AnimationAssets>,
     current_save: Res<CurrentSave>,
     control_input_map: Res<ControlInputMap>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    levels: Query<(&Handle<LdtkLevel>, &Transform)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut launch_options: ResMut<LaunchOptions>,
+    reloaded_player: Option<Res<ReloadedPlayer>>,
+    quick_loaded: Option<Res<QuickLoaded>>,
 ) {
     for (entity, transform, entity_instance) in entity_query.iter() {
         if entity_instance.identifier == *"Player" {
-            let hp: Health = if let Some(data) = current_save.0.data {
+            let mut transform = *transform;
+            let mut velocity = Velocity::zero();
+            let mut player = Player::default();
+            // set when the player is put back where it was in the selected level
+            let mut restored = false;
+
+            let mut hp: Health = if let Some(data) = &current_save.0.data {
+                player.facing_direction = data.facing_direction;
+
+                // the save may have been written in another level than the check point, or the
+                // game started in another level with `--level`
+                if let Some(last_check_point) =
+                    data.last_check_point.as_ref().filter(|last_check_point| {
+                        is_same_level(&last_check_point.level, &level_selection, &ldtk_assets)
+                    })
+                {
+                    // keep the z of the entity layer
+                    transform.translation = last_check_point
+                        .coordinate
+                        .truncate()
+                        .extend(transform.translation.z);
+
+                    cmd.entity(entity).insert(last_check_point.clone());
+                    restored = true;
+                }
+
+                // a quick load carries on from where the quick save was made
+                if quick_loaded.is_some() {
+                    if let Some(position) = &data.player_position {
+                        transform.translation = position.translation;
+                        velocity = Velocity::linear(position.linvel);
+                        restored = true;
+                    }
+
+                    cmd.remove_resource::<QuickLoaded>();
+                }
+
                 data.player_health
             } else {
                 entity_instance.into()
             };
 
+            // the player entity only exists in the first level, `--level` may select another one
+            if let Some((player_origin, selected_origin)) = level_origins(
+                entity_instance,
+                &level_selection,
+                &ldtk_assets,
+                &levels,
+                &ldtk_levels,
+            ) {
+                let level_position = match launch_options.spawn.take() {
+                    Some(spawn) => Some(spawn),
+                    // keep the spot of the player start, in the selected level
+                    None if !restored => Some(transform.translation.truncate() - player_origin),
+                    None => None,
+                };
+
+                if let Some(level_position) = level_position {
+                    transform.translation = (selected_origin + level_position - player_origin)
+                        .extend(transform.translation.z);
+                }
+            }
+
+            // the level was respawned by a hot reload, carry on from where the player was
+            if let Some(reloaded_player) = &reloaded_player {
+                transform = reloaded_player.transform;
+                hp = reloaded_player.health;
+                player.facing_direction = reloaded_player.facing_direction;
+
+                if let Some(last_check_point) = &reloaded_player.last_check_point {
+                    cmd.entity(entity).insert(last_check_point.clone());
+                }
+
+                cmd.remove_resource::<ReloadedPlayer>();
+            }
+
             cmd.entity(entity).insert(PlayerBundle {
-                sprite_bundle: SpriteBundle {
-                    texture: image_assets.player.clone(),
-                    transform: *transform,
+                sprite_sheet_bundle: SpriteSheetBundle {
+                    transform,
                     ..default()
                 },
+                animation: Animation::new(animation_assets.player.clone()),
                 player_physics_bundle: PlayerPhysicsBundle {
                     collider: Collider::cuboid(
                         entity_instance.width as f32 / 2.0,
@@ -407,16 +515,56 @@ pub(crate) fn spawn_player(
                     gravity_scale: GravityScale(1.0),
                     locked_axes: LockedAxes::ROTATION_LOCKED,
                     rigid_body: RigidBody::Dynamic,
-                    velocity: Velocity::zero(),
+                    velocity,
                     ccd: Ccd::enabled(),
                     ..default()
                 },
                 entity_instance: entity_instance.clone(),
                 input_manager: ControlInputManagerBundle::default()
                     .with_input_map(control_input_map.clone()),
+                player,
                 hp,
                 ..default()
             });
         }
     }
 }
+
+/// Origins of the level holding the player entity and of the selected level
+///
+/// An entity outside of the spawned levels, as in tests, is placed from the origin of the world.
+fn level_origins(
+    entity_instance: &EntityInstance,
+    level_selection: &LevelSelection,
+    ldtk_assets: &Assets<LdtkAsset>,
+    levels: &Query<(&Handle<LdtkLevel>, &Transform)>,
+    ldtk_levels: &Assets<LdtkLevel>,
+) -> Option<(Vec2, Vec2)> {
+    let selected_iid = &selected_level(level_selection, ldtk_assets)?.iid;
+    let mut player_origin = Vec2::ZERO;
+    let mut selected_origin = None;
+
+    for (level_handle, level_transform) in levels.iter() {
+        if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+            let level = &ldtk_level.level;
+            let origin = level_transform.translation.truncate();
+
+            let holds_player = level
+                .layer_instances
+                .iter()
+                .flatten()
+                .flat_map(|layer| &layer.entity_instances)
+                .any(|e| e.iid == entity_instance.iid);
+
+            if holds_player {
+                player_origin = origin;
+            }
+
+            if level.iid == *selected_iid {
+                selected_origin = Some(origin);
+            }
+        }
+    }
+
+    Some((player_origin, selected_origin?))
+}
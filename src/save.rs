@@ -1,88 +1,385 @@
-use bevy::{ecs::system::SystemState, prelude::*, tasks::IoTaskPool};
+use anyhow::bail;
+use bevy::{
+    ecs::system::SystemState,
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use chrono::{Local, TimeZone};
+use futures_lite::future;
 use std::{
+    collections::{HashMap, HashSet},
     fs,
-    io::{Cursor, Error, Write},
-    path::{Path, PathBuf},
+    path::PathBuf,
+    time::SystemTime,
 };
 
-use rmp_serde::{Deserializer, Serializer};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    player::{Health, Player},
-    state::{AppState, ConditionSet, IntoConditionalSystem},
+    cli::LaunchOptions,
+    physics::Velocity,
+    player::{DeathEvent, Direction, Health, Label as PlayerLabel, Player},
+    state::{AppLooplessStateExt, AppState, ConditionSet, IntoConditionalSystem, PauseState},
+    tilemap::{
+        check_point::LastCheckPoint, option_level_selection, selected_level, LdtkAsset,
+        LevelSelection, WorldConfig,
+    },
 };
 
-const SAVE_DIR: &str = "saves";
+pub(crate) mod autosave;
+pub(crate) mod backup;
+mod dirs;
+mod error;
+#[cfg(feature = "debug")]
+pub(crate) mod export;
+pub(crate) mod format;
+mod legacy;
+pub(crate) mod quick;
+
+use autosave::AutosavePlugin;
+pub(crate) use backup::{
+    backup_path, load_with_fallback, remove_with_backups, write_with_backups, LoadedFrom,
+    BACKUP_GENERATIONS,
+};
+pub(crate) use dirs::{migrate_legacy_dirs, DataDirs};
+pub(crate) use error::FileError;
+pub(crate) use format::{decode, load_file, load_metadata, save_file, Versioned};
+use legacy::{SaveDataV1, SaveDataV2};
+use quick::QuickSavePlugin;
+
+/// number of save slots, slot ids go from 0 to `MAX_SLOTS - 1`
+pub(crate) const MAX_SLOTS: usize = 5;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum SaveStatus {
+    /// nothing has been saved in the slot yet
+    #[default]
+    Empty,
+    Ok,
+    /// the save itself is damaged, `data` comes from a backup
+    Restored,
+    /// neither the save nor any of its backups can be read
+    Damaged,
+}
 
 #[derive(Clone, Debug, Default)]
 pub(crate) struct Save {
-    pub(crate) filename: String,
-    pub(crate) path: Option<PathBuf>,
+    /// `None` when the game was started without a save slot
+    pub(crate) slot: Option<usize>,
+    pub(crate) metadata: Option<SaveMetadata>,
     pub(crate) data: Option<SaveData>,
+    pub(crate) status: SaveStatus,
+    /// the last attempt to write the progress to the slot failed
+    pub(crate) unsaved: bool,
+}
+
+impl Save {
+    pub(crate) fn empty(slot: usize) -> Self {
+        Self {
+            slot: Some(slot),
+            ..default()
+        }
+    }
+
+    fn load(data_dirs: &DataDirs, slot: usize) -> Self {
+        let path = data_dirs.slot_path(slot);
+
+        let (data, metadata, status) = match load_with_fallback(&path) {
+            Ok((data, loaded_from)) => {
+                let metadata = load_metadata(loaded_from.path(&path)).ok();
+                let status = if loaded_from == LoadedFrom::Primary {
+                    SaveStatus::Ok
+                } else {
+                    SaveStatus::Restored
+                };
+
+                (Some(data), metadata, status)
+            }
+            Err(err) => {
+                error!("save {:?} is damaged: {err}", path);
+                (None, None, SaveStatus::Damaged)
+            }
+        };
+
+        Self {
+            slot: Some(slot),
+            metadata,
+            data,
+            status,
+            ..default()
+        }
+    }
+
+    pub(crate) fn path(&self, data_dirs: &DataDirs) -> Option<PathBuf> {
+        self.slot.map(|slot| data_dirs.slot_path(slot))
+    }
+
+    /// Name given by the player, or a default one derived from the slot
+    pub(crate) fn name(&self) -> String {
+        match (&self.metadata, self.slot) {
+            (Some(metadata), _) if !metadata.name.is_empty() => metadata.name.clone(),
+            (_, Some(slot)) => format!("Save {}", slot + 1),
+            _ => "Unsaved".to_string(),
+        }
+    }
+}
+
+/// Summary of a save, stored in the header so it can be read without the whole `SaveData`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct SaveMetadata {
+    /// slot name given by the player
+    pub(crate) name: String,
+    /// seconds spent in game
+    pub(crate) playtime: f64,
+    /// unix timestamp in seconds
+    pub(crate) saved_at: u64,
+    pub(crate) level_name: String,
+    pub(crate) deaths: u32,
+    /// share of visited levels, 0 to 100
+    pub(crate) completion: f32,
+}
+
+impl SaveMetadata {
+    pub(crate) fn playtime_label(&self) -> String {
+        let minutes = (self.playtime / 60.0) as u64;
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+
+    pub(crate) fn saved_at_label(&self) -> String {
+        Local
+            .timestamp_opt(self.saved_at as i64, 0)
+            .single()
+            .map(|saved_at| saved_at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
 }
 
+/// Statistics of the running game, written into `SaveMetadata`
+#[derive(Debug, Default, Resource)]
+pub(crate) struct SaveStats {
+    pub(crate) playtime: f64,
+    pub(crate) deaths: u32,
+}
+
+/// Where the player was and how it was moving, quick loads put it back there
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub(crate) struct PlayerPosition {
+    pub(crate) translation: Vec3,
+    pub(crate) linvel: Vec2,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub(crate) struct SaveData {
     pub(crate) player_health: Health,
+    pub(crate) facing_direction: Direction,
+    pub(crate) last_check_point: Option<LastCheckPoint>,
+    /// `None` when migrated from a save without it, the game then starts in
+    /// `WorldConfig::start_level`
+    #[serde(with = "option_level_selection")]
+    pub(crate) level: Option<LevelSelection>,
+    pub(crate) levels: LevelStates,
+    /// `None` for saves written before it was recorded
+    pub(crate) player_position: Option<PlayerPosition>,
 }
 
-pub(crate) struct SaveEvent;
+impl SaveData {
+    /// The level to play in, the start level of the world for migrated saves
+    pub(crate) fn level(&self, world_config: &WorldConfig) -> LevelSelection {
+        self.level
+            .clone()
+            .unwrap_or_else(|| world_config.start_level.clone())
+    }
+}
 
-#[derive(Default, Deref, DerefMut)]
-pub(crate) struct SaveSlots(pub(crate) Vec<Save>);
+impl Versioned for SaveData {
+    const VERSION: u32 = 3;
 
-impl SaveSlots {
-    pub(crate) fn new_save() -> Save {
-        let current_saves = Self::get_saves();
-        let path = PathBuf::from(format!("{}/save_{}.bin", SAVE_DIR, current_saves.len()));
-
-        Save {
-            filename: path.file_prefix().unwrap().to_str().unwrap().to_owned(),
-            path: Some(path),
-            data: None,
+    fn migrate(version: u32, payload: &[u8]) -> Result<Self, FileError> {
+        match version {
+            // saves written before the envelope share the layout of version 1
+            0 | 1 => decode::<SaveDataV1>(payload).map(Self::from),
+            2 => decode::<SaveDataV2>(payload).map(Self::from),
+            _ => Err(FileError::UnknownVersion(version)),
+        }
+    }
+}
+
+impl From<SaveDataV1> for SaveData {
+    fn from(save_data: SaveDataV1) -> Self {
+        Self {
+            player_health: save_data.player_health,
+            facing_direction: Player::default().facing_direction,
+            last_check_point: None,
+            level: None,
+            levels: LevelStates::default(),
+            player_position: None,
+        }
+    }
+}
+
+impl From<SaveDataV2> for SaveData {
+    fn from(save_data: SaveDataV2) -> Self {
+        Self {
+            player_health: save_data.player_health,
+            facing_direction: save_data.facing_direction,
+            last_check_point: save_data.last_check_point,
+            level: save_data.level,
+            levels: save_data.levels,
+            player_position: None,
         }
     }
+}
 
-    pub(crate) fn get_saves() -> Self {
-        let saves = if let Ok(entries) = fs::read_dir(SAVE_DIR) {
-            let mut saves: Vec<PathBuf> = entries
-                .filter(|e| e.is_ok())
-                .map(|e| e.unwrap().path())
-                .filter(|e| e.is_file())
-                .collect();
+/// Progress made in a single level
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct LevelState {
+    /// iids of killed enemies
+    pub(crate) dead_enemies: HashSet<String>,
+    /// keys of touched check points
+    pub(crate) activated_check_points: HashSet<String>,
+}
 
-            saves.sort();
+/// Progress of every visited level, keyed by level iid
+#[derive(Clone, Debug, Default, Deref, DerefMut, Deserialize, Resource, Serialize)]
+pub(crate) struct LevelStates(pub(crate) HashMap<String, LevelState>);
 
-            saves
-                .into_iter()
-                .map(|path| Save {
-                    filename: path.file_prefix().unwrap().to_str().unwrap().to_owned(),
-                    path: Some(path),
-                    data: None,
-                })
-                .collect()
-        } else {
-            vec![]
-        };
+impl LevelStates {
+    pub(crate) fn is_enemy_dead(&self, level_iid: &str, enemy_iid: &str) -> bool {
+        self.get(level_iid)
+            .map_or(false, |level| level.dead_enemies.contains(enemy_iid))
+    }
 
-        Self(saves)
+    pub(crate) fn is_check_point_activated(&self, level_iid: &str, key: &str) -> bool {
+        self.get(level_iid)
+            .map_or(false, |level| level.activated_check_points.contains(key))
     }
+}
 
-    pub(crate) fn new(length: usize) -> Self {
-        let mut saves = Self::get_saves();
+pub(crate) struct SaveEvent;
+
+/// What a background save task was writing
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SaveTarget {
+    Slot(usize),
+    /// the quick save kept next to a slot
+    QuickSave(usize),
+    Config,
+}
+
+/// Sent once a save task has written its file
+#[derive(Debug)]
+pub(crate) struct SaveCompleted {
+    pub(crate) target: SaveTarget,
+}
 
-        let mut new_saves: Vec<Save> = (saves.len()..length)
-            .map(|_| Save {
-                filename: "New Save".to_string(),
-                path: None,
-                data: None,
-            })
-            .collect();
+/// Sent when a save task fails, nothing has been overwritten in that case
+#[derive(Debug)]
+pub(crate) struct SaveError {
+    pub(crate) target: SaveTarget,
+    pub(crate) error: FileError,
+}
 
-        saves.append(&mut new_saves);
+/// Save tasks running on the `IoTaskPool`, polled by `poll_save_tasks`
+#[derive(Default, Resource)]
+pub(crate) struct SaveTasks(Vec<(SaveTarget, Task<Result<(), FileError>>)>);
+
+impl SaveTasks {
+    pub(crate) fn spawn<F>(&mut self, target: SaveTarget, write: F)
+    where
+        F: FnOnce() -> Result<(), FileError> + Send + 'static,
+    {
+        let task = IoTaskPool::get().spawn(async move { write() });
+        self.0.push((target, task));
+    }
 
-        saves
+    /// A save slot or a quick save is being written
+    pub(crate) fn is_saving(&self) -> bool {
+        self.0
+            .iter()
+            .any(|(target, _)| matches!(target, SaveTarget::Slot(_) | SaveTarget::QuickSave(_)))
+    }
+}
+
+/// seconds which have to pass between two saves, later `SaveEvent`s are merged into one save
+const SAVE_DEBOUNCE: f64 = 2.0;
+
+#[derive(Debug, Default, Resource)]
+struct SaveRequest {
+    /// a `SaveEvent` arrived and has not been handled yet
+    pending: bool,
+    /// elapsed seconds when `save_system` last ran
+    last_save: Option<f64>,
+}
+
+#[derive(Default, Deref, DerefMut)]
+pub(crate) struct SaveSlots(pub(crate) Vec<Save>);
+
+impl SaveSlots {
+    fn is_used(data_dirs: &DataDirs, slot: usize) -> bool {
+        let path = data_dirs.slot_path(slot);
+
+        // the save itself may be gone while its backups are still there
+        path.exists()
+            || (1..=BACKUP_GENERATIONS).any(|generation| backup_path(&path, generation).exists())
+    }
+
+    /// Every slot, empty ones included
+    pub(crate) fn get_saves(data_dirs: &DataDirs) -> Self {
+        Self(
+            (0..MAX_SLOTS)
+                .map(|slot| {
+                    if Self::is_used(data_dirs, slot) {
+                        Save::load(data_dirs, slot)
+                    } else {
+                        Save::empty(slot)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Overwrite the slot `to` with the slot `from`, the overwritten save is kept as a backup
+    pub(crate) fn copy(data_dirs: &DataDirs, from: usize, to: usize) -> anyhow::Result<()> {
+        let save = Save::load(data_dirs, from);
+        let name = save.name();
+
+        match (save.data, save.metadata) {
+            (Some(data), metadata) => {
+                let mut metadata = metadata.unwrap_or_default();
+                metadata.name = format!("{name} (copy)");
+
+                Ok(write_with_backups(
+                    data_dirs.slot_path(to),
+                    &metadata,
+                    &data,
+                )?)
+            }
+            _ => bail!("slot {from} has nothing to copy"),
+        }
+    }
+
+    pub(crate) fn rename(data_dirs: &DataDirs, slot: usize, name: String) -> anyhow::Result<()> {
+        let save = Save::load(data_dirs, slot);
+
+        match (save.data, save.metadata) {
+            (Some(data), metadata) => {
+                let mut metadata = metadata.unwrap_or_default();
+                metadata.name = name;
+
+                Ok(write_with_backups(
+                    data_dirs.slot_path(slot),
+                    &metadata,
+                    &data,
+                )?)
+            }
+            _ => bail!("slot {slot} has nothing to rename"),
+        }
+    }
+
+    pub(crate) fn delete(data_dirs: &DataDirs, slot: usize) -> std::io::Result<()> {
+        remove_with_backups(data_dirs.quick_save_path(slot))?;
+        remove_with_backups(data_dirs.slot_path(slot))
     }
 }
 
@@ -93,77 +390,314 @@ pub(crate) struct SavePlugin;
 
 impl Plugin for SavePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<CurrentSave>()
+        app.init_resource::<DataDirs>()
+            .add_startup_system(load_launch_slot)
+            .init_resource::<CurrentSave>()
+            .init_resource::<LevelStates>()
+            .init_resource::<SaveTasks>()
+            .init_resource::<SaveRequest>()
+            .add_plugin(AutosavePlugin)
+            .add_plugin(QuickSavePlugin)
             .add_event::<SaveEvent>()
+            .add_event::<SaveCompleted>()
+            .add_event::<SaveError>()
+            .add_system(poll_save_tasks)
+            .add_system(mark_unsaved.after(poll_save_tasks))
+            .init_resource::<SaveStats>()
+            .add_enter_system(AppState::InGame, restore_save)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .with_system(request_save)
+                    .with_system(save_system.run_if(save_due))
+                    .with_system(track_visited_levels)
+                    .into(),
+            )
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .run_if_not(PauseState::is_paused)
+                    .with_system(update_play_time)
+                    .into(),
+            )
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(AppState::InGame)
-                    .with_system(save_system.run_if(on_save_event))
+                    .run_if_not(PauseState::is_paused)
+                    .after(PlayerLabel::DeathSystems)
+                    .with_system(count_deaths)
                     .into(),
             );
     }
 }
 
-fn on_save_event(save_events: EventReader<SaveEvent>) -> bool {
-    if save_events.is_empty() {
-        false
-    } else {
+fn poll_save_tasks(
+    mut save_tasks: ResMut<SaveTasks>,
+    mut completed_events: EventWriter<SaveCompleted>,
+    mut error_events: EventWriter<SaveError>,
+) {
+    save_tasks.0.retain_mut(
+        |(target, task)| match future::block_on(future::poll_once(task)) {
+            Some(Ok(())) => {
+                debug!("saved {:?}", target);
+                completed_events.send(SaveCompleted { target: *target });
+                false
+            }
+            Some(Err(error)) => {
+                error!("cannot save {:?}: {error}", target);
+                error_events.send(SaveError {
+                    target: *target,
+                    error,
+                });
+                false
+            }
+            None => true,
+        },
+    );
+}
+
+/// Keep track of whether the progress of the current slot made it to disk
+fn mark_unsaved(
+    mut completed_events: EventReader<SaveCompleted>,
+    mut error_events: EventReader<SaveError>,
+    mut current_save: ResMut<CurrentSave>,
+) {
+    let slot = current_save.slot.map(SaveTarget::Slot);
+
+    for SaveCompleted { target } in completed_events.iter() {
+        if Some(*target) == slot {
+            current_save.unsaved = false;
+        }
+    }
+
+    for SaveError { target, .. } in error_events.iter() {
+        if Some(*target) == slot {
+            current_save.unsaved = true;
+        }
+    }
+}
+
+fn request_save(save_events: EventReader<SaveEvent>, mut save_request: ResMut<SaveRequest>) {
+    if !save_events.is_empty() {
         save_events.clear();
-        true
+        save_request.pending = true;
     }
 }
 
-fn save_system(world: &mut World) {
-    let mut system_state: SystemState<(Res<CurrentSave>, Query<&Health, With<Player>>)> =
-        SystemState::new(world);
+fn save_due(time: Res<Time>, save_request: Res<SaveRequest>, save_tasks: Res<SaveTasks>) -> bool {
+    // writing the same slot twice at once would fight over its temporary file
+    save_request.pending
+        && !save_tasks.is_saving()
+        && save_request.last_save.map_or(true, |last_save| {
+            time.elapsed_seconds_f64() - last_save >= SAVE_DEBOUNCE
+        })
+}
 
-    let (current_save, player_query) = system_state.get(world);
+/// Play the slot given with `--slot`
+fn load_launch_slot(
+    mut current_save: ResMut<CurrentSave>,
+    launch_options: Res<LaunchOptions>,
+    data_dirs: Res<DataDirs>,
+) {
+    if let Some(slot) = launch_options.slot {
+        current_save.0 = Save::load(&data_dirs, slot);
+    }
+}
 
-    if let Some(savefile) = current_save.path.clone() {
-        let player_health = player_query.single();
+/// Bring back the level and the progress of the loaded save, the player itself is restored in
+/// `spawn_player`
+fn restore_save(
+    mut cmd: Commands,
+    current_save: Res<CurrentSave>,
+    mut launch_options: ResMut<LaunchOptions>,
+    world_config: Res<WorldConfig>,
+) {
+    if let Some(data) = &current_save.data {
+        cmd.insert_resource(data.level(&world_config));
+        cmd.insert_resource(data.levels.clone());
+    } else {
+        cmd.insert_resource(world_config.start_level.clone());
+        cmd.insert_resource(LevelStates::default());
+    }
 
-        let save_data = SaveData {
-            player_health: *player_health,
-        };
-        IoTaskPool::get()
-            .spawn(async move {
-                debug!("saving {:?} to {:?}", save_data, savefile);
-                fs::create_dir_all(SAVE_DIR).expect("Error while creating the *saves* folder");
+    // only the first game of a launch starts in the level given with `--level`
+    if let Some(level) = launch_options.level.take() {
+        cmd.insert_resource(level);
+    }
 
-                let tmp_file = format!("{}_tmp", savefile.display());
+    cmd.insert_resource(SaveRequest::default());
 
-                save_file(&tmp_file, 0, &save_data).expect("Error while saving");
+    let metadata = current_save.metadata.clone().unwrap_or_default();
 
-                if fs::rename(tmp_file, savefile).is_err() {
-                    error!("cannot rename tmp save file");
+    cmd.insert_resource(SaveStats {
+        playtime: metadata.playtime,
+        deaths: metadata.deaths,
+    });
+}
+
+fn update_play_time(time: Res<Time>, mut save_stats: ResMut<SaveStats>) {
+    save_stats.playtime += time.delta_seconds_f64();
+}
+
+/// Count the players which started dying this frame
+///
+/// Death systems keep sending events every frame until the player is moved back to a check point,
+/// or as long as its health is gone.
+fn count_deaths(
+    mut death_events: EventReader<DeathEvent>,
+    mut dying: Local<HashSet<Entity>>,
+    mut save_stats: ResMut<SaveStats>,
+) {
+    let dead = death_events
+        .iter()
+        .map(|DeathEvent(entity)| *entity)
+        .collect::<HashSet<_>>();
+
+    save_stats.deaths += dead.difference(&dying).count() as u32;
+    *dying = dead;
+}
+
+fn track_visited_levels(
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut level_states: ResMut<LevelStates>,
+) {
+    if let Some(level) = selected_level(&level_selection, &ldtk_assets) {
+        if !level_states.contains_key(&level.iid) {
+            level_states.insert(level.iid.clone(), LevelState::default());
+        }
+    }
+}
+
+fn save_system(world: &mut World) {
+    let now = world.resource::<Time>().elapsed_seconds_f64();
+
+    let mut save_request = world.resource_mut::<SaveRequest>();
+    save_request.pending = false;
+    save_request.last_save = Some(now);
+
+    if let Some((slot, metadata, save_data)) = snapshot(world) {
+        let savefile = world.resource::<DataDirs>().slot_path(slot);
+
+        world
+            .resource_mut::<SaveTasks>()
+            .spawn(SaveTarget::Slot(slot), move || {
+                debug!("saving {:?} to {:?}", save_data, savefile);
+                if let Some(save_dir) = savefile.parent() {
+                    fs::create_dir_all(save_dir)?;
                 }
-            })
-            .detach();
-    } else {
-        debug!("cannot save {:?}", current_save);
+
+                write_with_backups(&savefile, &metadata, &save_data)
+            });
     }
 }
 
-pub(crate) fn save_file<P, T>(filepath: P, _version: u32, data: &T) -> Result<Vec<u8>, Error>
-where
-    T: Serialize,
-    P: AsRef<Path>,
-{
-    let mut buf = vec![];
-    data.serialize(&mut Serializer::new(&mut buf)).unwrap();
-    fs::File::create(filepath).and_then(|mut file| file.write(&buf))?;
-    Ok(buf)
+/// Progress of the running game, `None` when there is no slot or no player to save
+fn snapshot(world: &mut World) -> Option<(usize, SaveMetadata, SaveData)> {
+    let mut system_state: SystemState<(
+        Res<CurrentSave>,
+        Res<LevelSelection>,
+        Res<LevelStates>,
+        Res<SaveStats>,
+        Res<Assets<LdtkAsset>>,
+        Query<(
+            &Health,
+            &Player,
+            Option<&LastCheckPoint>,
+            &Transform,
+            &Velocity,
+        )>,
+    )> = SystemState::new(world);
+
+    let (current_save, level_selection, level_states, save_stats, ldtk_assets, player_query) =
+        system_state.get(world);
+
+    let slot = match current_save.slot {
+        Some(slot) => slot,
+        None => {
+            debug!("cannot save {:?}", current_save);
+            return None;
+        }
+    };
+
+    let (player_health, player, last_check_point, transform, velocity) =
+        match player_query.get_single() {
+            Ok(player) => player,
+            Err(err) => {
+                warn!("cannot save without a player: {err}");
+                return None;
+            }
+        };
+
+    let total_levels = ldtk_assets
+        .iter()
+        .map(|(_, ldtk_asset)| ldtk_asset.project.levels.len())
+        .sum::<usize>()
+        .max(1);
+
+    let metadata = SaveMetadata {
+        name: current_save
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.name.clone())
+            .unwrap_or_default(),
+        playtime: save_stats.playtime,
+        saved_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs()),
+        level_name: selected_level(&level_selection, &ldtk_assets)
+            .map(|level| level.identifier.clone())
+            .unwrap_or_default(),
+        deaths: save_stats.deaths,
+        completion: (level_states.len() as f32 / total_levels as f32 * 100.0).min(100.0),
+    };
+
+    let save_data = SaveData {
+        player_health: *player_health,
+        facing_direction: player.facing_direction,
+        last_check_point: last_check_point.cloned(),
+        level: Some(level_selection.clone()),
+        levels: level_states.clone(),
+        player_position: Some(PlayerPosition {
+            translation: transform.translation,
+            linvel: velocity.linvel,
+        }),
+    };
+
+    Some((slot, metadata, save_data))
 }
 
-pub(crate) fn load_file<'a, P, T>(filepath: P, _version: u32) -> anyhow::Result<T>
-where
-    T: Deserialize<'a>,
-    P: AsRef<Path>,
-{
-    let buf = fs::read(filepath)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn one_death_counts_once() {
+        let mut app = TestApp::new();
+        app.spawn_ground(Vec2::new(0., 0.), Vec2::new(200., 8.));
+        let player = app.spawn_player(Vec2::new(0., 24.), 1);
+
+        // the health stays at 0, death events keep coming
+        app.world
+            .get_mut::<Health>(player)
+            .expect("player has no Health")
+            .current = 0;
+        app.step_seconds(1.);
+
+        assert_eq!(app.world.resource::<SaveStats>().deaths, 1);
+    }
 
-    let cur = Cursor::new(buf.as_slice());
-    let mut de = Deserializer::new(cur);
+    #[test]
+    fn migrated_save_starts_in_the_start_level() {
+        let data = SaveData::from(SaveDataV1 {
+            player_health: Health::new(3),
+        });
+        let world_config = WorldConfig {
+            start_level: LevelSelection::Identifier("Start".to_string()),
+            ..default()
+        };
 
-    Ok(Deserialize::deserialize(&mut de)?)
+        assert_eq!(data.level(&world_config), world_config.start_level);
+    }
 }
@@ -0,0 +1,145 @@
+//! Policies deciding when progress is saved without the player asking for it
+//!
+//! Every trigger only sends a [`SaveEvent`], bursts of them are merged by the debounce in
+//! [`SaveRequest`](super::SaveRequest). Exiting the game is the exception, the save is written
+//! before the app closes.
+use bevy::{app::AppExit, prelude::*};
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use super::{save_system, CurrentSave, SaveCompleted, SaveEvent, SaveTarget, SaveTasks};
+use crate::{
+    state::{AppLooplessStateExt, AppState, ConditionSet, CurrentState, PauseState},
+    tilemap::LevelTransition,
+    ui::menu::GameConfig,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct AutosaveConfig {
+    /// seconds of play between two saves, `None` turns the timer off
+    pub(crate) interval: Option<f32>,
+    pub(crate) on_level_transition: bool,
+    pub(crate) on_pause: bool,
+    pub(crate) on_exit: bool,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Some(120.0),
+            on_level_transition: true,
+            on_pause: true,
+            on_exit: true,
+        }
+    }
+}
+
+/// Autosave bookkeeping of the running game
+#[derive(Debug, Default, Resource)]
+struct AutosaveState {
+    /// seconds played since the last save
+    timer: f32,
+}
+
+pub(crate) struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppState::InGame, reset_autosave)
+            .add_enter_system(PauseState::On, autosave_on_pause)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .run_if_not(PauseState::is_paused)
+                    .with_system(autosave_on_timer)
+                    .with_system(autosave_on_level_transition)
+                    .into(),
+            )
+            // `AppExit` is handled at the end of the frame it is sent in, right before the app
+            // closes
+            .add_system_to_stage(CoreStage::Last, save_on_exit);
+    }
+}
+
+fn reset_autosave(mut cmd: Commands) {
+    cmd.insert_resource(AutosaveState::default());
+}
+
+fn autosave_on_timer(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut autosave_state: ResMut<AutosaveState>,
+    mut completed_events: EventReader<SaveCompleted>,
+    mut save_event: EventWriter<SaveEvent>,
+) {
+    // any save restarts the countdown
+    if completed_events
+        .iter()
+        .any(|completed| matches!(completed.target, SaveTarget::Slot(_)))
+    {
+        autosave_state.timer = 0.0;
+    }
+
+    if let Some(interval) = game_config.autosave.interval {
+        autosave_state.timer += time.delta_seconds();
+
+        if autosave_state.timer >= interval {
+            autosave_state.timer = 0.0;
+            save_event.send(SaveEvent);
+        }
+    }
+}
+
+/// Loading a save or a quick save selects its level as well, only walking into a level counts
+fn autosave_on_level_transition(
+    transition_events: EventReader<LevelTransition>,
+    game_config: Res<GameConfig>,
+    mut save_event: EventWriter<SaveEvent>,
+) {
+    if !transition_events.is_empty() {
+        transition_events.clear();
+
+        if game_config.autosave.on_level_transition {
+            save_event.send(SaveEvent);
+        }
+    }
+}
+
+fn autosave_on_pause(
+    app_state: Res<CurrentState<AppState>>,
+    game_config: Res<GameConfig>,
+    mut save_event: EventWriter<SaveEvent>,
+) {
+    if app_state.0 == AppState::InGame && game_config.autosave.on_pause {
+        save_event.send(SaveEvent);
+    }
+}
+
+/// Write the save right away and wait for it, there is no next frame to poll the task in
+fn save_on_exit(world: &mut World) {
+    if world.resource::<Events<AppExit>>().is_empty()
+        || world.resource::<CurrentState<AppState>>().0 != AppState::InGame
+        || world.resource::<CurrentSave>().slot.is_none()
+        || !world.resource::<GameConfig>().autosave.on_exit
+    {
+        return;
+    }
+
+    // a save still being written would fight the new one over its temporary file
+    if world.resource::<SaveTasks>().is_saving() {
+        finish_save_tasks(world);
+    }
+
+    save_system(world);
+    finish_save_tasks(world);
+}
+
+fn finish_save_tasks(world: &mut World) {
+    for (target, task) in world.resource_mut::<SaveTasks>().0.drain(..) {
+        match future::block_on(task) {
+            Ok(()) => info!("saved {:?} before exiting", target),
+            Err(error) => error!("cannot save {:?} before exiting: {error}", target),
+        }
+    }
+}
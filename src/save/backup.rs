@@ -0,0 +1,215 @@
+//! Rolling backups kept next to every file written through [`write_with_backups`]
+//!
+//! `save_0.bin` is backed up as `save_0.bin.bak1` (newest) up to `save_0.bin.bak3` (oldest).
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use super::{load_file, save_file, FileError, Versioned};
+
+/// how many older copies are kept for every file
+pub(crate) const BACKUP_GENERATIONS: usize = 3;
+
+/// Where a loaded file came from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum LoadedFrom {
+    Primary,
+    Backup(usize),
+}
+
+impl LoadedFrom {
+    /// The file which was actually read for `path`
+    pub(crate) fn path<P: AsRef<Path>>(self, path: P) -> PathBuf {
+        match self {
+            Self::Primary => path.as_ref().to_path_buf(),
+            Self::Backup(generation) => backup_path(path, generation),
+        }
+    }
+}
+
+pub(crate) fn backup_path<P: AsRef<Path>>(path: P, generation: usize) -> PathBuf {
+    let mut backup = path.as_ref().as_os_str().to_owned();
+    backup.push(format!(".bak{generation}"));
+    PathBuf::from(backup)
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push("_tmp");
+    PathBuf::from(tmp)
+}
+
+/// Write `data` through a temporary file and shift the previous copy into the backups.
+pub(crate) fn write_with_backups<P, M, T>(path: P, metadata: &M, data: &T) -> Result<(), FileError>
+where
+    T: Versioned,
+    M: Serialize,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let tmp_file = tmp_path(path);
+
+    save_file(&tmp_file, metadata, data)?;
+
+    // make sure the new file can be read back before it replaces anything
+    load_file::<_, T>(&tmp_file)?;
+
+    rotate_backups::<T>(path)?;
+    fs::rename(tmp_file, path)?;
+
+    Ok(())
+}
+
+fn rotate_backups<T: Versioned>(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    // a damaged file must not push the good backups out
+    if let Err(err) = load_file::<_, T>(path) {
+        warn!("discarding {:?}: {err}", path);
+        return fs::remove_file(path);
+    }
+
+    for generation in (1..BACKUP_GENERATIONS).rev() {
+        let backup = backup_path(path, generation);
+        if backup.exists() {
+            fs::rename(backup, backup_path(path, generation + 1))?;
+        }
+    }
+
+    fs::rename(path, backup_path(path, 1))
+}
+
+/// Load `path`, falling back to the newest backup which is still valid.
+///
+/// The error of the primary file is returned if none of the backups can be read.
+pub(crate) fn load_with_fallback<P, T>(path: P) -> Result<(T, LoadedFrom), FileError>
+where
+    T: Versioned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    let err = match load_file(path) {
+        Ok(data) => return Ok((data, LoadedFrom::Primary)),
+        Err(err) => err,
+    };
+
+    warn!("cannot load {:?}: {err}", path);
+
+    for generation in 1..=BACKUP_GENERATIONS {
+        if let Ok(data) = load_file(backup_path(path, generation)) {
+            info!("restored {:?} from backup {generation}", path);
+            return Ok((data, LoadedFrom::Backup(generation)));
+        }
+    }
+
+    Err(err)
+}
+
+/// Remove a file together with all of its backups
+pub(crate) fn remove_with_backups<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+
+    for generation in 1..=BACKUP_GENERATIONS {
+        let backup = backup_path(path, generation);
+        if backup.exists() {
+            fs::remove_file(backup)?;
+        }
+    }
+
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::testing::test_dir;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Data {
+        value: u32,
+    }
+
+    impl Versioned for Data {
+        const VERSION: u32 = 1;
+
+        fn migrate(version: u32, _: &[u8]) -> Result<Self, FileError> {
+            Err(FileError::UnknownVersion(version))
+        }
+    }
+
+    fn flip_last_byte(path: &Path) {
+        let mut buf = fs::read(path).unwrap();
+        *buf.last_mut().unwrap() ^= 0xff;
+        fs::write(path, buf).unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = test_dir("backup_round_trip").join("save.bin");
+
+        write_with_backups(&path, &(), &Data { value: 1 }).unwrap();
+        write_with_backups(&path, &(), &Data { value: 2 }).unwrap();
+
+        assert_eq!(
+            load_with_fallback::<_, Data>(&path).unwrap(),
+            (Data { value: 2 }, LoadedFrom::Primary)
+        );
+        assert_eq!(
+            load_file::<_, Data>(backup_path(&path, 1)).unwrap(),
+            Data { value: 1 }
+        );
+        assert!(!tmp_path(&path).exists());
+    }
+
+    #[test]
+    fn damaged_file_falls_back_to_newest_backup() {
+        let path = test_dir("backup_fallback").join("save.bin");
+
+        write_with_backups(&path, &(), &Data { value: 1 }).unwrap();
+        write_with_backups(&path, &(), &Data { value: 2 }).unwrap();
+        flip_last_byte(&path);
+
+        assert!(matches!(
+            load_file::<_, Data>(&path),
+            Err(FileError::Damaged(_))
+        ));
+        assert_eq!(
+            load_with_fallback::<_, Data>(&path).unwrap(),
+            (Data { value: 1 }, LoadedFrom::Backup(1))
+        );
+    }
+
+    #[test]
+    fn damaged_file_doesnt_push_out_backups() {
+        let path = test_dir("backup_rotation").join("save.bin");
+
+        for value in 1..=BACKUP_GENERATIONS as u32 + 1 {
+            write_with_backups(&path, &(), &Data { value }).unwrap();
+        }
+        flip_last_byte(&path);
+
+        // the damaged primary is discarded instead of becoming `.bak1`
+        write_with_backups(&path, &(), &Data { value: 10 }).unwrap();
+
+        assert_eq!(
+            load_file::<_, Data>(backup_path(&path, 1)).unwrap(),
+            Data {
+                value: BACKUP_GENERATIONS as u32
+            }
+        );
+        assert_eq!(load_file::<_, Data>(&path).unwrap(), Data { value: 10 });
+    }
+}
@@ -0,0 +1,189 @@
+//! Where saves and the config live
+//!
+//! By default they go to the data and config directories of the platform, e.g.
+//! `~/.local/share/reckoning/saves` and `~/.config/reckoning` on Linux. A data directory given
+//! with `--data-dir` or `RECKONING_DATA_DIR` holds both instead, in the same `saves` and `data`
+//! layout older versions used in the working directory.
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+
+/// env var overriding the data directory
+pub(crate) const DATA_DIR_ENV: &str = "RECKONING_DATA_DIR";
+
+/// directories relative to the working directory which older versions wrote to
+const LEGACY_SAVE_DIR: &str = "saves";
+const LEGACY_CONFIG_DIR: &str = "data";
+
+const CONFIG_FILENAME: &str = "config.bin";
+
+/// start of the names of slot saves, quick saves and their backups
+const SAVE_FILE_PREFIX: &str = "save_";
+
+#[derive(Clone, Debug, Resource)]
+pub(crate) struct DataDirs {
+    pub(crate) saves: PathBuf,
+    pub(crate) config: PathBuf,
+}
+
+impl Default for DataDirs {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl DataDirs {
+    /// `data_dir` takes precedence over the env var, which takes precedence over the platform
+    /// directories
+    pub(crate) fn new(data_dir: Option<PathBuf>) -> Self {
+        if let Some(data_dir) = data_dir.or_else(|| env::var_os(DATA_DIR_ENV).map(PathBuf::from)) {
+            return Self::portable(&data_dir);
+        }
+
+        match ProjectDirs::from("", "", "reckoning") {
+            Some(project_dirs) => Self {
+                saves: project_dirs.data_dir().join("saves"),
+                config: project_dirs.config_dir().to_path_buf(),
+            },
+            None => {
+                warn!("no home directory found, using the working directory");
+                Self::portable(Path::new("."))
+            }
+        }
+    }
+
+    fn portable(data_dir: &Path) -> Self {
+        Self {
+            saves: data_dir.join(LEGACY_SAVE_DIR),
+            config: data_dir.join(LEGACY_CONFIG_DIR),
+        }
+    }
+
+    pub(crate) fn slot_path(&self, slot: usize) -> PathBuf {
+        self.saves.join(format!("{SAVE_FILE_PREFIX}{slot}.bin"))
+    }
+
+    pub(crate) fn quick_save_path(&self, slot: usize) -> PathBuf {
+        self.saves
+            .join(format!("{SAVE_FILE_PREFIX}{slot}.quick.bin"))
+    }
+
+    pub(crate) fn config_path(&self) -> PathBuf {
+        self.config.join(CONFIG_FILENAME)
+    }
+}
+
+/// Move files left in `./saves` and `./data` by older versions to the current directories.
+///
+/// Only the files the game writes are moved, other programs may use directories with such common
+/// names. Nothing is moved into a directory which already has files, so a second install can't
+/// overwrite the progress of another one.
+///
+/// The game calls it at launch, apps embedding `ReckoningPlugins` and tests keep the working
+/// directory as it is.
+pub(crate) fn migrate_legacy_dirs(data_dirs: &DataDirs) {
+    for (legacy, prefix, current) in [
+        (LEGACY_SAVE_DIR, SAVE_FILE_PREFIX, &data_dirs.saves),
+        (LEGACY_CONFIG_DIR, CONFIG_FILENAME, &data_dirs.config),
+    ] {
+        let legacy = Path::new(legacy);
+
+        if !legacy.is_dir() || is_same_dir(legacy, current) || has_entries(current) {
+            continue;
+        }
+
+        match move_files(legacy, current, prefix) {
+            Ok(0) => (),
+            Ok(moved) => info!("moved {moved} file(s) from {:?} to {:?}", legacy, current),
+            Err(err) => error!("cannot move {:?} to {:?}: {err}", legacy, current),
+        }
+    }
+}
+
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn has_entries(dir: &Path) -> bool {
+    fs::read_dir(dir).map_or(false, |mut entries| entries.next().is_some())
+}
+
+/// Move the files of `from` whose name starts with `prefix`, returns how many were moved
+fn move_files(from: &Path, to: &Path, prefix: &str) -> io::Result<usize> {
+    let mut files = vec![];
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let is_game_file = entry
+            .file_name()
+            .to_str()
+            .map_or(false, |name| name.starts_with(prefix));
+
+        if is_game_file && entry.file_type()?.is_file() {
+            files.push(entry);
+        }
+    }
+
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    fs::create_dir_all(to)?;
+
+    for entry in &files {
+        let target = to.join(entry.file_name());
+
+        // renaming fails across file systems
+        if fs::rename(entry.path(), &target).is_err() {
+            fs::copy(entry.path(), &target)?;
+            fs::remove_file(entry.path())?;
+        }
+    }
+
+    // only goes away when nothing else was in it
+    let _ = fs::remove_dir(from);
+
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_dir;
+
+    #[test]
+    fn only_files_of_the_game_are_moved() {
+        let dir = test_dir("dirs_only_game_files");
+        let (legacy, current) = (dir.join("data"), dir.join("config"));
+        fs::create_dir(&legacy).unwrap();
+        for name in [CONFIG_FILENAME, "config.bin.bak1", "notes.txt"] {
+            fs::write(legacy.join(name), name).unwrap();
+        }
+
+        assert_eq!(move_files(&legacy, &current, CONFIG_FILENAME).unwrap(), 2);
+
+        assert!(current.join(CONFIG_FILENAME).is_file());
+        assert!(current.join("config.bin.bak1").is_file());
+        assert!(legacy.join("notes.txt").is_file());
+        assert!(!current.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn directory_without_files_of_the_game_is_left_alone() {
+        let dir = test_dir("dirs_no_game_files");
+        let (legacy, current) = (dir.join("data"), dir.join("config"));
+        fs::create_dir(&legacy).unwrap();
+        fs::write(legacy.join("notes.txt"), "notes").unwrap();
+
+        assert_eq!(move_files(&legacy, &current, CONFIG_FILENAME).unwrap(), 0);
+
+        assert!(!current.exists());
+        assert!(legacy.join("notes.txt").is_file());
+    }
+}
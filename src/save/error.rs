@@ -0,0 +1,23 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Why a file could not be written or read back
+#[derive(Debug, Error)]
+pub(crate) enum FileError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("cannot serialize: {0}")]
+    Serialize(#[from] rmp_serde::encode::Error),
+    #[error("cannot deserialize: {0}")]
+    Deserialize(#[from] rmp_serde::decode::Error),
+    /// checksum mismatch or truncated file
+    #[error("file is damaged: {0}")]
+    Damaged(String),
+    /// written by a newer version of the game
+    #[error("format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// no migration exists for this version
+    #[error("unknown format version {0}")]
+    UnknownVersion(u32),
+}
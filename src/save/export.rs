@@ -0,0 +1,124 @@
+//! Human readable copies of saves and config files, to inspect or hand edit them
+//!
+//! `.ron` and `.json` files hold a [`Document`], files with any other extension are read and
+//! written in the binary format of [`format`](super::format).
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    load_metadata, load_with_fallback, write_with_backups, SaveData, SaveMetadata, Versioned,
+};
+use crate::ui::menu::GameConfig;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Format {
+    Binary,
+    Ron,
+    Json,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Self::Ron,
+            Some("json") => Self::Json,
+            _ => Self::Binary,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub(crate) enum FileKind {
+    Save,
+    Config,
+}
+
+impl FileKind {
+    /// Files named like `config.bin` are configs, anything else is taken as a save
+    fn from_path(path: &Path) -> Self {
+        match path.file_prefix().and_then(|prefix| prefix.to_str()) {
+            Some("config") => Self::Config,
+            _ => Self::Save,
+        }
+    }
+}
+
+/// Layout of the readable formats
+#[derive(Deserialize, Serialize)]
+struct Document<M, T> {
+    /// format version of `data`, imports have to match the current one
+    version: u32,
+    metadata: M,
+    data: T,
+}
+
+fn read<M, T>(path: &Path) -> anyhow::Result<(M, T)>
+where
+    M: DeserializeOwned + Default,
+    T: Versioned,
+{
+    let document: Document<M, T> = match Format::from_path(path) {
+        Format::Binary => {
+            let (data, loaded_from) = load_with_fallback(path)?;
+            let metadata = load_metadata(loaded_from.path(path))?;
+
+            return Ok((metadata, data));
+        }
+        Format::Ron => ron::from_str(&fs::read_to_string(path)?)?,
+        Format::Json => serde_json::from_str(&fs::read_to_string(path)?)?,
+    };
+
+    if document.version != T::VERSION {
+        bail!(
+            "document has format version {}, only version {} can be imported",
+            document.version,
+            T::VERSION
+        );
+    }
+
+    Ok((document.metadata, document.data))
+}
+
+fn write<M, T>(path: &Path, metadata: &M, data: &T) -> anyhow::Result<()>
+where
+    M: Serialize,
+    T: Versioned,
+{
+    let document = Document {
+        version: T::VERSION,
+        metadata,
+        data,
+    };
+
+    let text = match Format::from_path(path) {
+        // reads the file back before replacing the previous one
+        Format::Binary => return Ok(write_with_backups(path, metadata, data)?),
+        Format::Ron => ron::ser::to_string_pretty(&document, ron::ser::PrettyConfig::default())?,
+        Format::Json => serde_json::to_string_pretty(&document)?,
+    };
+
+    Ok(fs::write(path, text)?)
+}
+
+fn convert_as<M, T>(input: &Path, output: &Path) -> anyhow::Result<()>
+where
+    M: DeserializeOwned + Serialize + Default,
+    T: Versioned,
+{
+    let (metadata, data) =
+        read::<M, T>(input).with_context(|| format!("cannot read {}", input.display()))?;
+
+    write(output, &metadata, &data).with_context(|| format!("cannot write {}", output.display()))
+}
+
+/// Convert between the binary and the readable formats, picked from the file extensions.
+///
+/// `kind` is guessed from the name of `input` when it is not given.
+pub(crate) fn convert(input: &Path, output: &Path, kind: Option<FileKind>) -> anyhow::Result<()> {
+    match kind.unwrap_or_else(|| FileKind::from_path(input)) {
+        FileKind::Save => convert_as::<SaveMetadata, SaveData>(input, output),
+        FileKind::Config => convert_as::<(), GameConfig>(input, output),
+    }
+}
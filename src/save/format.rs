@@ -0,0 +1,301 @@
+//! On-disk layout shared by every file written through [`save_file`]
+//!
+//! ```text
+//! | magic (4 bytes) | format version (u32, LE) | CRC32 (u32, LE) | metadata length (u32, LE) |
+//! | metadata (MessagePack map) | payload (MessagePack) |
+//! ```
+//!
+//! The checksum covers the metadata and the payload. The metadata is a small summary which can be
+//! read with [`load_metadata`] without touching the payload. It is written as a map, so fields can
+//! be added to it without a format version bump.
+//!
+//! Files written before the envelope existed are plain MessagePack and are reported as version 0.
+//! They carry no checksum and no metadata.
+use std::{
+    fs,
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+
+use rmp_serde::{Deserializer, Serializer};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::FileError;
+
+const MAGIC: &[u8; 4] = b"RKNG";
+const HEADER_LEN: usize = MAGIC.len() + 4 + 4 + 4;
+
+/// A type that can be written to disk and upgraded from older layouts.
+pub(crate) trait Versioned: Serialize + DeserializeOwned {
+    /// version written into the envelope of newly saved files
+    const VERSION: u32;
+
+    /// Upgrade a payload written with an older `version` to the current layout.
+    ///
+    /// Implementations decode the legacy layout of `version` and convert it forward one step at a
+    /// time until it reaches `Self`.
+    fn migrate(version: u32, payload: &[u8]) -> Result<Self, FileError>;
+}
+
+pub(crate) fn encode<T: Serialize>(data: &T) -> Result<Vec<u8>, FileError> {
+    let mut buf = vec![];
+    data.serialize(&mut Serializer::new(&mut buf))?;
+    Ok(buf)
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(payload: &[u8]) -> Result<T, FileError> {
+    let cur = Cursor::new(payload);
+    let mut de = Deserializer::new(cur);
+
+    Ok(Deserialize::deserialize(&mut de)?)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().expect("slice is 4 bytes"))
+}
+
+struct Header {
+    version: u32,
+    checksum: u32,
+    metadata_len: usize,
+}
+
+impl Header {
+    /// `None` when the file was written before the envelope existed
+    fn parse(buf: &[u8]) -> Result<Option<Self>, FileError> {
+        match buf.strip_prefix(MAGIC) {
+            Some(rest) => {
+                if buf.len() < HEADER_LEN {
+                    return Err(FileError::Damaged(format!(
+                        "file is truncated, header is {} bytes",
+                        buf.len()
+                    )));
+                }
+
+                Ok(Some(Self {
+                    version: read_u32(rest),
+                    checksum: read_u32(&rest[4..]),
+                    metadata_len: read_u32(&rest[8..]) as usize,
+                }))
+            }
+            // legacy files are a MessagePack value, which never starts like the magic
+            None if MAGIC.starts_with(buf) => Err(FileError::Damaged(format!(
+                "file is truncated, header is {} bytes",
+                buf.len()
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Split a file into its format version and payload, verifying the checksum on the way
+fn open_envelope(buf: &[u8]) -> Result<(u32, &[u8]), FileError> {
+    let header = match Header::parse(buf)? {
+        Some(header) => header,
+        None => return Ok((0, buf)),
+    };
+
+    let body = &buf[HEADER_LEN..];
+
+    let actual = crc32fast::hash(body);
+    if header.checksum != actual {
+        return Err(FileError::Damaged(format!(
+            "checksum mismatch, expected {:#010x} got {actual:#010x}",
+            header.checksum
+        )));
+    }
+
+    if body.len() < header.metadata_len {
+        return Err(FileError::Damaged("metadata is truncated".to_string()));
+    }
+
+    Ok((header.version, &body[header.metadata_len..]))
+}
+
+pub(crate) fn save_file<P, M, T>(filepath: P, metadata: &M, data: &T) -> Result<Vec<u8>, FileError>
+where
+    T: Versioned,
+    M: Serialize,
+    P: AsRef<Path>,
+{
+    let mut body = vec![];
+    metadata.serialize(&mut Serializer::new(&mut body).with_struct_map())?;
+    let metadata_len = body.len() as u32;
+    body.extend_from_slice(&encode(data)?);
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + body.len());
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&T::VERSION.to_le_bytes());
+    buf.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+    buf.extend_from_slice(&metadata_len.to_le_bytes());
+    buf.extend_from_slice(&body);
+
+    fs::File::create(filepath).and_then(|mut file| file.write_all(&buf))?;
+    Ok(buf)
+}
+
+pub(crate) fn load_file<P, T>(filepath: P) -> Result<T, FileError>
+where
+    T: Versioned,
+    P: AsRef<Path>,
+{
+    let buf = fs::read(filepath)?;
+
+    let (version, payload) = open_envelope(&buf)?;
+
+    if version == T::VERSION {
+        decode(payload)
+    } else if version > T::VERSION {
+        Err(FileError::UnsupportedVersion {
+            found: version,
+            supported: T::VERSION,
+        })
+    } else {
+        T::migrate(version, payload)
+    }
+}
+
+/// Read the metadata of a file without reading its payload.
+///
+/// The checksum is not verified, files written before the envelope existed yield `M::default()`.
+pub(crate) fn load_metadata<P, M>(filepath: P) -> Result<M, FileError>
+where
+    M: DeserializeOwned + Default,
+    P: AsRef<Path>,
+{
+    let mut file = fs::File::open(filepath)?;
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    file.by_ref()
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+
+    let header = match Header::parse(&header)? {
+        Some(header) => header,
+        None => return Ok(M::default()),
+    };
+
+    // the header isn't covered by the checksum, a damaged length must not be allocated
+    let body_len = file.metadata()?.len().saturating_sub(HEADER_LEN as u64);
+    if header.metadata_len as u64 > body_len {
+        return Err(FileError::Damaged("metadata is truncated".to_string()));
+    }
+
+    let mut metadata = vec![0; header.metadata_len];
+    file.read_exact(&mut metadata)?;
+
+    decode(&metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_dir;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Data {
+        value: u32,
+    }
+
+    impl Versioned for Data {
+        const VERSION: u32 = 1;
+
+        fn migrate(version: u32, payload: &[u8]) -> Result<Self, FileError> {
+            match version {
+                // files without the envelope are a bare number
+                0 => Ok(Self {
+                    value: decode(payload)?,
+                }),
+                _ => Err(FileError::UnknownVersion(version)),
+            }
+        }
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+    struct Metadata {
+        name: String,
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = test_dir("format_round_trip").join("save.bin");
+        let metadata = Metadata {
+            name: "slot".to_string(),
+        };
+
+        save_file(&path, &metadata, &Data { value: 42 }).unwrap();
+
+        assert_eq!(load_file::<_, Data>(&path).unwrap(), Data { value: 42 });
+        assert_eq!(load_metadata::<_, Metadata>(&path).unwrap(), metadata);
+    }
+
+    #[test]
+    fn flipped_byte_is_damaged() {
+        let path = test_dir("format_flipped_byte").join("save.bin");
+
+        let mut buf = save_file(&path, &(), &Data { value: 42 }).unwrap();
+        *buf.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, buf).unwrap();
+
+        assert!(matches!(
+            load_file::<_, Data>(&path),
+            Err(FileError::Damaged(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_file_is_damaged() {
+        let path = test_dir("format_truncated").join("save.bin");
+
+        let buf = save_file(&path, &(), &Data { value: 42 }).unwrap();
+
+        // cut inside the body, then inside the header
+        for len in [buf.len() - 1, HEADER_LEN - 2] {
+            fs::write(&path, &buf[..len]).unwrap();
+
+            assert!(
+                matches!(load_file::<_, Data>(&path), Err(FileError::Damaged(_))),
+                "file of {len} bytes isn't reported as damaged"
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_metadata_length_is_damaged() {
+        let path = test_dir("format_metadata_length").join("save.bin");
+
+        let mut buf = save_file(&path, &(), &Data { value: 42 }).unwrap();
+        buf[HEADER_LEN - 4..HEADER_LEN].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, buf).unwrap();
+
+        assert!(matches!(
+            load_metadata::<_, Metadata>(&path),
+            Err(FileError::Damaged(_))
+        ));
+    }
+
+    #[test]
+    fn file_without_magic_is_version_0() {
+        let path = test_dir("format_legacy").join("save.bin");
+
+        let payload = encode(&7_u32).unwrap();
+        fs::write(&path, &payload).unwrap();
+
+        assert_eq!(open_envelope(&payload).unwrap(), (0, payload.as_slice()));
+        assert_eq!(load_file::<_, Data>(&path).unwrap(), Data { value: 7 });
+        assert_eq!(
+            load_metadata::<_, Metadata>(&path).unwrap(),
+            Metadata::default()
+        );
+    }
+
+    #[test]
+    fn cut_magic_is_damaged() {
+        for len in 0..MAGIC.len() {
+            assert!(
+                matches!(open_envelope(&MAGIC[..len]), Err(FileError::Damaged(_))),
+                "file of {len} bytes isn't reported as damaged"
+            );
+        }
+    }
+}
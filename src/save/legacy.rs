@@ -0,0 +1,27 @@
+//! Layouts of [`SaveData`](super::SaveData) written by older versions of the game
+//!
+//! They are only decoded by migrations and converted forward to the current layout.
+use serde::{Deserialize, Serialize};
+
+use super::LevelStates;
+use crate::{
+    player::{Direction, Health},
+    tilemap::{check_point::LastCheckPoint, option_level_selection, LevelSelection},
+};
+
+/// version 0 (no envelope) and version 1
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub(crate) struct SaveDataV1 {
+    pub(crate) player_health: Health,
+}
+
+/// version 2, before the position of the player was saved
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct SaveDataV2 {
+    pub(crate) player_health: Health,
+    pub(crate) facing_direction: Direction,
+    pub(crate) last_check_point: Option<LastCheckPoint>,
+    #[serde(with = "option_level_selection")]
+    pub(crate) level: Option<LevelSelection>,
+    pub(crate) levels: LevelStates,
+}
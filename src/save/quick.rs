@@ -0,0 +1,127 @@
+//! Quick saves are kept next to the save of a slot and restored without leaving the game
+use std::fs;
+
+use bevy::prelude::*;
+
+use super::{
+    load_metadata, load_with_fallback, snapshot, write_with_backups, CurrentSave, DataDirs,
+    SaveData, SaveMetadata, SaveStats, SaveTarget, SaveTasks,
+};
+use crate::{
+    input::{ControlAction, ControlActionState},
+    player::Player,
+    state::{AppLooplessStateExt, AppState, ConditionSet, IntoConditionalSystem},
+    tilemap::{LdtkAsset, Respawn, WorldConfig},
+};
+
+pub(crate) struct QuickSaveEvent;
+
+pub(crate) struct QuickLoadEvent;
+
+/// Taken by `spawn_player`, which puts the player where the quick save was made
+#[derive(Debug, Resource)]
+pub(crate) struct QuickLoaded;
+
+pub(crate) struct QuickSavePlugin;
+
+impl Plugin for QuickSavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<QuickSaveEvent>()
+            .add_event::<QuickLoadEvent>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .with_system(send_quick_events)
+                    .with_system(quick_save.run_on_event::<QuickSaveEvent>())
+                    .with_system(quick_load.run_on_event::<QuickLoadEvent>())
+                    .into(),
+            )
+            .add_exit_system(AppState::InGame, clean_up);
+    }
+}
+
+fn send_quick_events(
+    players: Query<&ControlActionState, With<Player>>,
+    mut quick_save_event: EventWriter<QuickSaveEvent>,
+    mut quick_load_event: EventWriter<QuickLoadEvent>,
+) {
+    for action_state in players.iter() {
+        if action_state.just_pressed(ControlAction::QuickSave) {
+            quick_save_event.send(QuickSaveEvent);
+        } else if action_state.just_pressed(ControlAction::QuickLoad) {
+            quick_load_event.send(QuickLoadEvent);
+        }
+    }
+}
+
+fn quick_save(world: &mut World) {
+    // writing the same file twice at once would fight over its temporary file
+    if world.resource::<SaveTasks>().is_saving() {
+        warn!("cannot quick save while another save is being written");
+        return;
+    }
+
+    if let Some((slot, metadata, save_data)) = snapshot(world) {
+        let savefile = world.resource::<DataDirs>().quick_save_path(slot);
+
+        world
+            .resource_mut::<SaveTasks>()
+            .spawn(SaveTarget::QuickSave(slot), move || {
+                debug!("quick saving {:?} to {:?}", save_data, savefile);
+                if let Some(save_dir) = savefile.parent() {
+                    fs::create_dir_all(save_dir)?;
+                }
+
+                write_with_backups(&savefile, &metadata, &save_data)
+            });
+    }
+}
+
+/// Put the game back to the quick save of the current slot.
+///
+/// The LDtk world is respawned, `spawn_player` and `spawn_npc` then rebuild the player, its check
+/// point and the enemies from the restored `CurrentSave` and `LevelStates`. The player is put back
+/// where it was, not at its check point.
+fn quick_load(
+    mut cmd: Commands,
+    mut current_save: ResMut<CurrentSave>,
+    data_dirs: Res<DataDirs>,
+    world_config: Res<WorldConfig>,
+    ldtk_worlds: Query<Entity, With<Handle<LdtkAsset>>>,
+) {
+    let slot = match current_save.slot {
+        Some(slot) => slot,
+        None => return,
+    };
+
+    let path = data_dirs.quick_save_path(slot);
+    if !path.exists() {
+        info!("slot {slot} has no quick save");
+        return;
+    }
+
+    match load_with_fallback::<_, SaveData>(&path) {
+        Ok((data, loaded_from)) => {
+            let metadata: SaveMetadata = load_metadata(loaded_from.path(&path)).unwrap_or_default();
+
+            cmd.insert_resource(data.level(&world_config));
+            cmd.insert_resource(data.levels.clone());
+            cmd.insert_resource(SaveStats {
+                playtime: metadata.playtime,
+                deaths: metadata.deaths,
+            });
+
+            current_save.data = Some(data);
+            cmd.insert_resource(QuickLoaded);
+
+            for ldtk_world in ldtk_worlds.iter() {
+                cmd.entity(ldtk_world).insert(Respawn);
+            }
+        }
+        Err(err) => error!("cannot load quick save {:?}: {err}", path),
+    }
+}
+
+fn clean_up(mut cmd: Commands) {
+    cmd.remove_resource::<QuickLoaded>();
+}
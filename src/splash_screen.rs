@@ -2,7 +2,8 @@ use bevy::prelude::*;
 
 use crate::{
     asset::FontAssets,
-    state::{AppLooplessStateExt, AppState, IntoConditionalSystem, NextState},
+    state::{AppLooplessStateExt, AppState, IntoConditionalSystem},
+    ui::transition::{TransitionCommandsExt, TransitionEffect},
 };
 
 #[derive(Deref, DerefMut, Resource)]
@@ -46,6 +47,6 @@ fn splash_setup(mut cmd: Commands, font_assets: Res<FontAssets>) {
 
 fn countdown(mut cmd: Commands, time: Res<Time>, mut timer: ResMut<SplashTimer>) {
     if timer.tick(time.delta()).finished() {
-        cmd.insert_resource(NextState(AppState::MainMenuAssetLoading));
+        cmd.transition_to(AppState::MainMenuAssetLoading, TransitionEffect::Fade);
     }
 }
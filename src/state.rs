@@ -1,11 +1,12 @@
-use std::env;
-
 use bevy::prelude::*;
 use global_state::{AddGlobalState, AddTransientState, GlobalState, TransientState};
 pub(crate) use iyes_loopless::prelude::*;
 
-// GlobalState will despawn all compoents on state exit, unless they are marked with Persistent
-#[derive(Clone, Copy, Debug, Eq, Hash, GlobalState, PartialEq, Resource)]
+use crate::cli::LaunchOptions;
+
+// on exit, GlobalState despawns root entities that are neither Persistent nor StateScoped<AppState>,
+// unless the variant is marked no_cleanup
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, GlobalState, PartialEq, Resource)]
 pub(crate) enum AppState {
     InGame,
     InGameAssetLoading,
@@ -13,31 +14,10 @@ pub(crate) enum AppState {
     MainMenuAssetLoading,
     SaveMenu,
     Splash,
+    #[default]
     SplashAssetLoading,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        // game state can be controlled during cargo run
-        //
-        // ❯ GAMESTATE=InGame cargo run
-        if let Ok(state) = env::var("APPSTATE") {
-            match state.as_ref() {
-                "InGame" => Self::InGameAssetLoading,
-                "InGameAssetLoading" => Self::InGameAssetLoading,
-                "MainMenu" => Self::MainMenuAssetLoading,
-                "MainMenuAssetLoading" => Self::MainMenuAssetLoading,
-                "SaveMenu" => Self::SaveMenu,
-                "Splash" => Self::SplashAssetLoading,
-                "SplashAssetLoading" => Self::SplashAssetLoading,
-                _ => panic!("unrecognised game state {state}"),
-            }
-        } else {
-            Self::SplashAssetLoading
-        }
-    }
-}
-
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Resource, TransientState)]
 pub(crate) enum MenuState {
     Audio,
@@ -62,11 +42,23 @@ impl PauseState {
     }
 }
 
-pub(crate) struct StatePlugin;
+#[derive(Default)]
+pub(crate) struct StatePlugin {
+    /// state the app starts in, unless a launch option asks for another one
+    pub(crate) start_state: AppState,
+}
 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_loopless_state(AppState::default())
+        // `--state` and the other launch options can skip the splash and menus
+        let start_state = app
+            .world
+            .get_resource::<LaunchOptions>()
+            .and_then(LaunchOptions::start_state)
+            .unwrap_or(self.start_state);
+
+        app.init_resource::<LaunchOptions>()
+            .add_loopless_state(start_state)
             .add_loopless_state(MenuState::default())
             .add_loopless_state(PauseState::default())
             .add_global_state::<AppState>()
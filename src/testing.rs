@@ -0,0 +1,326 @@
+//! Headless app for testing the gameplay plugins
+//!
+//! Frames are stepped with a fixed delta so physics runs the same on every machine, and actions are
+//! pressed on the `ControlActionState` of entities instead of going through the input devices.
+use std::{
+    env, fs,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    time::Duration,
+};
+
+use bevy::{
+    asset::AssetPlugin, hierarchy::HierarchyPlugin, prelude::*, time::TimePlugin,
+    transform::TransformPlugin, utils::Instant,
+};
+use bevy_asset_loader::prelude::{StandardDynamicAsset, StandardDynamicAssetCollection};
+
+use crate::{
+    animation::AnimationPlugin,
+    asset::{AnimationAssets, AudioAssets},
+    audio::{music::LevelMusicPlugin, sfx::PlaySfx, AudioSource},
+    input::{ControlAction, ControlActionState},
+    npc::{MobBundle, MobPhysicsBundle, NpcPlugin},
+    physics::{Collider, GravityScale, PhysicsPlugin, RigidBody},
+    player::{Health, PlayerPlugin},
+    save::{DataDirs, SavePlugin},
+    state::{AppState, NextState, StatePlugin},
+    tilemap::{
+        ldtk::{self, FieldInstance},
+        level_assets::LevelAssetsPlugin,
+        EntityInstance, FieldValue, LdtkAsset, LdtkLevel, LevelEvent, LevelIid, LevelPlugin,
+    },
+    ui::menu::GameConfig,
+    weapon::WeaponPlugin,
+};
+
+/// delta of every frame, the one rapier steps with by default
+pub(crate) const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+const PLAYER_SIZE: i32 = 16;
+
+/// An empty directory of its own for a test, under the temporary directory of the system
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir()
+        .join(format!("reckoning-test-{}", std::process::id()))
+        .join(name);
+
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("cannot create the test directory");
+
+    dir
+}
+
+pub(crate) struct TestApp {
+    app: App,
+    now: Instant,
+    /// project the levels of `spawn_level` are added to
+    project: Handle<LdtkAsset>,
+}
+
+impl Deref for TestApp {
+    type Target = App;
+
+    fn deref(&self) -> &App {
+        &self.app
+    }
+}
+
+impl DerefMut for TestApp {
+    fn deref_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+impl TestApp {
+    /// An app in `AppState::InGame`, without window, renderer or audio
+    pub(crate) fn new() -> Self {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins.build().disable::<TimePlugin>())
+            .add_plugin(AssetPlugin {
+                asset_folder: concat!(env!("CARGO_MANIFEST_DIR"), "/assets").to_string(),
+                ..default()
+            })
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            // `LdtkPlugin` needs a renderer, the levels of a test are spawned by hand
+            .add_asset::<LdtkAsset>()
+            .add_asset::<LdtkLevel>()
+            .add_event::<LevelEvent>()
+            .add_asset::<StandardDynamicAssetCollection>()
+            // `AudioPlugin` needs an audio device, the sounds asked for are dropped
+            .add_asset::<AudioSource>()
+            .add_event::<PlaySfx>()
+            // keep saves of the tests away from the ones of the player
+            .insert_resource(DataDirs::new(Some(
+                env::temp_dir().join(format!("reckoning-test-{}", std::process::id())),
+            )))
+            .insert_resource(AnimationAssets {
+                player: Handle::default(),
+                mob: Handle::default(),
+            })
+            .insert_resource(AudioAssets {
+                bgm: Handle::default(),
+            })
+            .insert_resource(ControlAction::get_input_map())
+            .init_resource::<GameConfig>()
+            .add_plugin(StatePlugin::default())
+            .add_plugin(PhysicsPlugin::default())
+            .add_plugin(LevelPlugin)
+            .add_plugin(LevelAssetsPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(NpcPlugin)
+            .add_plugin(WeaponPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(SavePlugin)
+            .add_plugin(LevelMusicPlugin);
+
+        let project = app
+            .world
+            .resource_mut::<Assets<LdtkAsset>>()
+            .add(LdtkAsset {
+                project: default(),
+                tileset_map: default(),
+                level_map: default(),
+            });
+
+        let now = Instant::now();
+        let mut time = Time::default();
+        time.update_with_instant(now);
+        app.insert_resource(time);
+
+        let mut test_app = Self { app, now, project };
+
+        test_app.insert_resource(NextState(AppState::InGame));
+        test_app.step();
+
+        test_app
+    }
+
+    /// Run one frame of `FRAME`
+    pub(crate) fn step(&mut self) {
+        let previous = self.now;
+        self.now += FRAME;
+        self.world
+            .resource_mut::<Time>()
+            .update_with_instant(self.now);
+
+        self.app.update();
+
+        // what the input manager does before each frame, just pressed actions become held
+        let world = &mut self.app.world;
+        let mut action_states = world.query::<&mut ControlActionState>();
+        for mut action_state in action_states.iter_mut(world) {
+            action_state.tick(self.now, previous);
+        }
+    }
+
+    pub(crate) fn step_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    pub(crate) fn step_seconds(&mut self, seconds: f32) {
+        self.step_frames((seconds / FRAME.as_secs_f32()).ceil() as usize);
+    }
+
+    /// Hold `action` on `entity` until it is released
+    pub(crate) fn press(&mut self, entity: Entity, action: ControlAction) {
+        self.action_state(entity).press(action);
+    }
+
+    fn action_state(&mut self, entity: Entity) -> Mut<ControlActionState> {
+        self.world
+            .get_mut::<ControlActionState>(entity)
+            .expect("entity has no ControlActionState")
+    }
+
+    pub(crate) fn translation(&self, entity: Entity) -> Vec3 {
+        self.world
+            .get::<Transform>(entity)
+            .expect("entity has no Transform")
+            .translation
+    }
+
+    /// A static box, centered on `position`
+    pub(crate) fn spawn_ground(&mut self, position: Vec2, half_size: Vec2) -> Entity {
+        self.world
+            .spawn((
+                Name::new("Ground"),
+                RigidBody::Fixed,
+                Collider::cuboid(half_size.x, half_size.y),
+                TransformBundle::from(Transform::from_translation(position.extend(0.))),
+            ))
+            .id()
+    }
+
+    /// A manifest with `entries`, pairs of a key and a path, which lives as long as the handle
+    pub(crate) fn add_manifest(
+        &mut self,
+        entries: &[(&str, &str)],
+    ) -> Handle<StandardDynamicAssetCollection> {
+        let manifest = entries
+            .iter()
+            .map(|(key, path)| {
+                let asset = StandardDynamicAsset::File {
+                    path: path.to_string(),
+                };
+                (key.to_string(), asset)
+            })
+            .collect();
+
+        self.world
+            .resource_mut::<Assets<StandardDynamicAssetCollection>>()
+            .add(StandardDynamicAssetCollection(manifest))
+    }
+
+    /// A level of the project with its bottom left corner at `position`, as if LDtk had spawned it
+    ///
+    /// Its uid is `uid` and its iid `test-level-<uid>`, the selection starts at `Uid(0)`.
+    pub(crate) fn spawn_level(
+        &mut self,
+        uid: i32,
+        position: Vec2,
+        size: Vec2,
+        field_instances: Vec<FieldInstance>,
+    ) -> Entity {
+        let level = ldtk::Level {
+            identifier: format!("Level_{uid}"),
+            iid: format!("test-level-{uid}"),
+            uid,
+            world_x: position.x as i32,
+            px_wid: size.x as i32,
+            px_hei: size.y as i32,
+            field_instances,
+            ..default()
+        };
+
+        self.world
+            .resource_mut::<Assets<LdtkAsset>>()
+            .get_mut(&self.project)
+            .expect("the project of the test is gone")
+            .project
+            .levels
+            .push(level.clone());
+
+        let level_handle = self
+            .world
+            .resource_mut::<Assets<LdtkLevel>>()
+            .add(LdtkLevel {
+                level,
+                background_image: None,
+            });
+
+        self.world
+            .spawn((
+                level_handle,
+                TransformBundle::from(Transform::from_translation(position.extend(0.))),
+            ))
+            .id()
+    }
+
+    /// A player spawned by `spawn_player`, as if LDtk had spawned its entity
+    pub(crate) fn spawn_player(&mut self, position: Vec2, hp: i32) -> Entity {
+        let entity_instance = EntityInstance {
+            identifier: "Player".to_string(),
+            width: PLAYER_SIZE,
+            height: PLAYER_SIZE,
+            field_instances: vec![FieldInstance {
+                identifier: "HP".to_string(),
+                tile: None,
+                field_instance_type: "Int".to_string(),
+                value: FieldValue::Int(Some(hp)),
+                def_uid: 0,
+                real_editor_values: Vec::new(),
+            }],
+            ..default()
+        };
+
+        let player = self
+            .world
+            .spawn((
+                entity_instance,
+                TransformBundle::from(Transform::from_translation(position.extend(0.))),
+            ))
+            .id();
+
+        // `spawn_player` adds the rest
+        self.step();
+
+        player
+    }
+
+    /// A mob hovering at `position`, without patrol
+    ///
+    /// Its `EntityInstance` isn't named `Mob`, `spawn_npc` would look for it in the LDtk levels.
+    pub(crate) fn spawn_mob(&mut self, position: Vec2, hp: u32) -> Entity {
+        let entity_instance = EntityInstance {
+            identifier: "TestMob".to_string(),
+            iid: "test-mob".to_string(),
+            width: PLAYER_SIZE,
+            height: PLAYER_SIZE,
+            ..default()
+        };
+
+        let mut collider_bundle = MobPhysicsBundle::from(&entity_instance);
+        collider_bundle.gravity_scale = GravityScale(0.);
+
+        self.world
+            .spawn((
+                MobBundle {
+                    sprite_sheet_bundle: SpriteSheetBundle {
+                        transform: Transform::from_translation(position.extend(0.)),
+                        ..default()
+                    },
+                    collider_bundle,
+                    hp: Health::new(hp),
+                    ..default()
+                },
+                entity_instance,
+                LevelIid("test-level".to_string()),
+            ))
+            .id()
+    }
+}
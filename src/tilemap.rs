@@ -4,25 +4,192 @@ use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::*;
 pub use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     physics::{Collider, RigidBody},
+    player::Player,
     state::{AppLooplessStateExt, AppState, ConditionSet},
 };
 
 pub(crate) mod check_point;
+pub(crate) mod hot_reload;
+pub(crate) mod level_assets;
 use check_point::CheckPointPlugin;
+use hot_reload::HotReloadPlugin;
+use level_assets::LevelAssetsPlugin;
 
-#[derive(Debug, Default, Resource)]
-pub(crate) struct LevelSize(pub Option<Vec2>);
+const DEFAULT_LDTK_FILE: &str = "levels/reckoning.ldtk";
 
-pub struct TilemapPlugin;
+/// The LDtk project of the game and the level new games start in, set through `TilemapPlugin`
+#[derive(Clone, Debug, Resource)]
+pub(crate) struct WorldConfig {
+    pub(crate) ldtk_file: String,
+    pub(crate) start_level: LevelSelection,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            ldtk_file: DEFAULT_LDTK_FILE.to_string(),
+            start_level: LevelSelection::Uid(0),
+        }
+    }
+}
+
+/// Serde mirror of [`LevelSelection`], use with `#[serde(with = "LevelSelectionDef")]`
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "LevelSelection")]
+pub(crate) enum LevelSelectionDef {
+    Identifier(String),
+    Index(usize),
+    Iid(String),
+    Uid(i32),
+}
+
+/// Serde of an optional [`LevelSelection`], use with `#[serde(with = "option_level_selection")]`
+pub(crate) mod option_level_selection {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{LevelSelection, LevelSelectionDef};
+
+    #[derive(Deserialize, Serialize)]
+    struct Level(#[serde(with = "LevelSelectionDef")] LevelSelection);
+
+    pub(crate) fn serialize<S: Serializer>(
+        level: &Option<LevelSelection>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        level.clone().map(Level).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<LevelSelection>, D::Error> {
+        Ok(Option::<Level>::deserialize(deserializer)?.map(|Level(level)| level))
+    }
+}
+
+/// Iid of the LDtk level an entity was spawned in
+#[derive(Clone, Component, Debug, Default, Deref, Eq, Hash, PartialEq)]
+pub(crate) struct LevelIid(pub(crate) String);
+
+/// The level of the project which is currently selected
+pub(crate) fn selected_level<'a>(
+    level_selection: &LevelSelection,
+    ldtk_assets: &'a Assets<LdtkAsset>,
+) -> Option<&'a ldtk::Level> {
+    ldtk_assets.iter().find_map(|(_, ldtk_asset)| {
+        ldtk_asset
+            .project
+            .levels
+            .iter()
+            .enumerate()
+            .find(|(index, level)| level_selection.is_match(index, level))
+            .map(|(_, level)| level)
+    })
+}
+
+/// Whether two selections point to the same level, such as `Uid(0)` and the `Iid` of that level
+pub(crate) fn is_same_level(
+    a: &LevelSelection,
+    b: &LevelSelection,
+    ldtk_assets: &Assets<LdtkAsset>,
+) -> bool {
+    a == b
+        || match (
+            selected_level(a, ldtk_assets),
+            selected_level(b, ldtk_assets),
+        ) {
+            (Some(a), Some(b)) => a.iid == b.iid,
+            _ => false,
+        }
+}
+
+/// Sent when the player walks from one level into another, with the iids of both levels
+#[derive(Clone, Debug)]
+pub(crate) struct LevelTransition {
+    pub(crate) from: String,
+    pub(crate) to: String,
+}
+
+/// Select the level the player stands in
+///
+/// bevy_ecs_ldtk spawns the neighbours of the selected level, so walking over the edge of a level
+/// selects the next one and spawns its own neighbours.
+fn select_player_level(
+    players: Query<&GlobalTransform, With<Player>>,
+    levels: Query<(&Handle<LdtkLevel>, &GlobalTransform)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut transition_events: EventWriter<LevelTransition>,
+) {
+    let player_position = match players.get_single() {
+        Ok(transform) => transform.translation().truncate(),
+        Err(_) => return,
+    };
+
+    let current_iid = selected_level(&level_selection, &ldtk_assets).map(|level| level.iid.clone());
+
+    for (level_handle, level_transform) in levels.iter() {
+        if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+            let level = &ldtk_level.level;
+
+            // levels are anchored at their bottom left corner
+            let min = level_transform.translation().truncate();
+            let max = min + Vec2::new(level.px_wid as f32, level.px_hei as f32);
+            let contains_player = player_position.x >= min.x
+                && player_position.x < max.x
+                && player_position.y >= min.y
+                && player_position.y < max.y;
+
+            if contains_player && current_iid.as_ref() != Some(&level.iid) {
+                if let Some(from) = current_iid {
+                    transition_events.send(LevelTransition {
+                        from,
+                        to: level.iid.clone(),
+                    });
+                }
+                *level_selection = LevelSelection::Iid(level.iid.clone());
+                return;
+            }
+        }
+    }
+}
+
+/// Find the level and the layer which an entity instance belongs to
+pub(crate) fn find_entity_layer<'a>(
+    entity_instance: &EntityInstance,
+    level_query: &Query<&Handle<LdtkLevel>>,
+    levels: &'a Assets<LdtkLevel>,
+) -> Option<(&'a ldtk::Level, &'a LayerInstance)> {
+    level_query.iter().find_map(|handle| {
+        let level = &levels.get(handle)?.level;
+
+        level
+            .layer_instances
+            .iter()
+            .flatten()
+            .find(|layer| {
+                layer
+                    .entity_instances
+                    .iter()
+                    .any(|e| e.iid == entity_instance.iid)
+            })
+            .map(|layer| (level, layer))
+    })
+}
+
+#[derive(Default)]
+pub struct TilemapPlugin {
+    pub(crate) world: WorldConfig,
+}
 
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(LdtkPlugin)
-            .init_resource::<LevelSize>()
-            .insert_resource(LevelSelection::Uid(0))
+        app.insert_resource(self.world.clone())
+            .add_plugin(LdtkPlugin)
             .insert_resource(LdtkSettings {
                 level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
                     load_level_neighbors: true,
@@ -31,16 +198,30 @@ impl Plugin for TilemapPlugin {
                 ..default()
             })
             .add_enter_system(AppState::InGame, setup)
+            .add_plugin(HotReloadPlugin)
+            .add_plugin(LevelAssetsPlugin)
+            .add_plugin(LevelPlugin);
+    }
+}
+
+/// What the game builds on top of the spawned levels
+///
+/// Unlike `LdtkPlugin` it doesn't need a renderer, headless apps add it with the LDtk assets and
+/// `LevelEvent` registered by hand.
+pub(crate) struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldConfig>();
+        let start_level = app.world.resource::<WorldConfig>().start_level.clone();
+
+        app.insert_resource(start_level)
+            .add_event::<LevelTransition>()
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(AppState::InGame)
                     .with_system(spawn_wall_collision)
-                    .into(),
-            )
-            .add_system_set(
-                ConditionSet::new()
-                    .run_in_state(AppState::InGameAssetLoading)
-                    .with_system(set_boundary)
+                    .with_system(select_player_level)
                     .into(),
             )
             .add_plugin(CheckPointPlugin)
@@ -48,39 +229,18 @@ impl Plugin for TilemapPlugin {
     }
 }
 
-fn setup(mut cmd: Commands, asset_server: Res<AssetServer>) {
+fn setup(mut cmd: Commands, asset_server: Res<AssetServer>, world_config: Res<WorldConfig>) {
     cmd.spawn(Camera2dBundle::default());
 
     cmd.spawn((
         Name::new("Ldtk World"),
         LdtkWorldBundle {
-            ldtk_handle: asset_server.load("levels/reckoning.ldtk"),
+            ldtk_handle: asset_server.load(world_config.ldtk_file.as_str()),
             ..default()
         },
     ));
 }
 
-#[allow(clippy::only_used_in_recursion)]
-fn set_boundary(
-    mut level_events: EventReader<LevelEvent>,
-    mut level_size: ResMut<LevelSize>,
-    level_query: Query<&Handle<LdtkLevel>>,
-    levels: Res<Assets<LdtkLevel>>,
-) {
-    for event in level_events.iter() {
-        if let LevelEvent::Transformed(_) = event {
-            level_query.for_each(|level_handle| {
-                if let Some(level) = levels.get(level_handle) {
-                    *level_size = LevelSize(Some(Vec2::new(
-                        level.level.px_wid as f32,
-                        level.level.px_hei as f32,
-                    )));
-                }
-            })
-        }
-    }
-}
-
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub(crate) struct Wall;
 
@@ -270,3 +430,49 @@ pub(crate) fn spawn_wall_collision(
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::ControlAction, testing::TestApp};
+
+    #[test]
+    fn player_walks_into_the_next_level_but_not_off_the_world() {
+        let mut app = TestApp::new();
+        app.spawn_level(0, Vec2::new(0., 0.), Vec2::new(320., 160.), Vec::new());
+        app.spawn_level(1, Vec2::new(320., 0.), Vec2::new(320., 160.), Vec::new());
+        app.spawn_ground(Vec2::new(320., 8.), Vec2::new(400., 8.));
+        let player = app.spawn_player(Vec2::new(280., 32.), 3);
+        app.step_seconds(0.5);
+
+        let mut transition_reader = app.world.resource::<Events<LevelTransition>>().get_reader();
+        let mut transitions = Vec::new();
+
+        app.press(player, ControlAction::Right);
+        for _ in 0..60 {
+            app.step();
+            let transition_events = app.world.resource::<Events<LevelTransition>>();
+            transitions.extend(
+                transition_reader
+                    .iter(transition_events)
+                    .map(|transition| (transition.from.clone(), transition.to.clone())),
+            );
+        }
+
+        assert_eq!(
+            transitions,
+            [("test-level-0".to_string(), "test-level-1".to_string())]
+        );
+        assert_eq!(
+            *app.world.resource::<LevelSelection>(),
+            LevelSelection::Iid("test-level-1".to_string())
+        );
+
+        // the ground goes on, the last level doesn't
+        app.step_seconds(2.);
+        assert!(
+            app.translation(player).x <= 640.,
+            "player walked off the world"
+        );
+    }
+}
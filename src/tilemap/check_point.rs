@@ -1,27 +1,45 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+    audio::sfx::{PlaySfx, Sfx},
     physics::*,
     player::Player,
-    save::SaveEvent,
+    save::{LevelStates, SaveEvent},
     state::{AppState, ConditionSet},
 };
 
 use super::{
-    merge_grids, GridCoords, LayerInstance, LdtkIntCell, LdtkLevel, LevelSelection,
-    RegisterLdtkObjects,
+    merge_grids, GridCoords, LayerInstance, LdtkIntCell, LdtkLevel, LevelIid, LevelSelection,
+    LevelSelectionDef, RegisterLdtkObjects,
 };
 
-#[derive(Component, Debug, Default, Resource)]
-pub(crate) struct LastCheckPoint {
-    pub(crate) coordinate: Vec3,
-    pub(crate) level: LevelSelection,
+#[derive(Clone, Component, Debug, Default, Deserialize, Resource, Serialize)]
+pub struct LastCheckPoint {
+    pub coordinate: Vec3,
+    #[serde(with = "LevelSelectionDef")]
+    pub level: LevelSelection,
 }
 
 #[derive(Component, Debug, Default)]
 pub(crate) struct CheckPoint;
 
+/// Identifies a check point inside its level, stable between level spawns
+#[derive(Clone, Component, Debug, Deref)]
+pub(crate) struct CheckPointKey(pub(crate) String);
+
+impl CheckPointKey {
+    /// Named after the bottom left grid of the merged check point
+    fn new(left: i32, bottom: i32) -> Self {
+        Self(format!("{left}_{bottom}"))
+    }
+}
+
+/// Marks a check point which has been touched by the player
+#[derive(Component, Debug, Default)]
+pub(crate) struct ActivatedCheckPoint;
+
 #[derive(Bundle, Default, LdtkIntCell)]
 pub(crate) struct CheckPointBundle {
     pub(crate) check_point: CheckPoint,
@@ -89,16 +107,37 @@ fn save_initial_check_point(
 fn save_last_check_point(
     mut cmd: Commands,
     check_points: Query<
-        (&GlobalTransform, &CollidingEntities, &CheckPoint),
-        Changed<CollidingEntities>,
+        (
+            Entity,
+            &GlobalTransform,
+            &CollidingEntities,
+            &CheckPointKey,
+            &LevelIid,
+            Option<&ActivatedCheckPoint>,
+        ),
+        (With<CheckPoint>, Changed<CollidingEntities>),
     >,
     mut players: Query<(Entity, Option<&mut LastCheckPoint>), With<Player>>,
     level_selection: Res<LevelSelection>,
+    mut level_states: ResMut<LevelStates>,
     mut save_event: EventWriter<SaveEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
-    for (transform, colliding_entities, _) in check_points.iter() {
+    for (check_point_entity, transform, colliding_entities, key, level_iid, activated) in
+        check_points.iter()
+    {
         for (player_entity, mut last_check_point) in players.iter_mut() {
             if colliding_entities.contains(player_entity) {
+                level_states
+                    .entry(level_iid.0.clone())
+                    .or_default()
+                    .activated_check_points
+                    .insert(key.0.clone());
+                cmd.entity(check_point_entity).insert(ActivatedCheckPoint);
+                if activated.is_none() {
+                    sfx_events.send(PlaySfx(Sfx::CheckPoint));
+                }
+
                 upsert_check_point(
                     &mut cmd,
                     player_entity,
@@ -118,6 +157,7 @@ pub(crate) fn spawn_check_points(
     parent_query: Query<&Parent, Without<CheckPoint>>,
     level_query: Query<(Entity, &Handle<LdtkLevel>)>,
     levels: Res<Assets<LdtkLevel>>,
+    level_states: Res<LevelStates>,
 ) {
     if !check_points.is_empty() {
         let mut check_point_grid_coords: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
@@ -148,37 +188,45 @@ pub(crate) fn spawn_check_points(
 
                 let LayerInstance { grid_size, .. } = layer;
 
+                let level_iid = LevelIid(level.level.iid.clone());
+
                 // spawn colliders for every rectangle
                 for merged_grid in merge_grids(&layer, check_point_grid_coords) {
-                    let child_entity = commands
-                        .spawn((
-                            Name::new("CheckPoint"),
-                            CheckPoint,
-                            ActiveEvents::COLLISION_EVENTS,
-                            ActiveCollisionTypes::DYNAMIC_STATIC,
-                            CollidingEntities::default(),
-                            Sensor,
-                            Collider::cuboid(
-                                (merged_grid.right - merged_grid.left + 1) as f32
-                                    * grid_size as f32
-                                    / 2.,
-                                (merged_grid.top - merged_grid.bottom + 1) as f32
-                                    * grid_size as f32
-                                    / 2.,
-                            ),
-                            GravityScale(0.0),
-                            RigidBody::Fixed,
-                            TransformBundle::from(Transform::from_xyz(
-                                (merged_grid.left + merged_grid.right + 1) as f32
-                                    * grid_size as f32
-                                    / 2.,
-                                (merged_grid.bottom + merged_grid.top + 1) as f32
-                                    * grid_size as f32
-                                    / 2.,
-                                0.,
-                            )),
-                        ))
-                        .id();
+                    let key = CheckPointKey::new(merged_grid.left, merged_grid.bottom);
+
+                    let activated = level_states.is_check_point_activated(&level_iid, &key);
+
+                    let mut child_entity = commands.spawn((
+                        Name::new("CheckPoint"),
+                        CheckPoint,
+                        key,
+                        level_iid.clone(),
+                        ActiveEvents::COLLISION_EVENTS,
+                        ActiveCollisionTypes::DYNAMIC_STATIC,
+                        CollidingEntities::default(),
+                        Sensor,
+                        Collider::cuboid(
+                            (merged_grid.right - merged_grid.left + 1) as f32 * grid_size as f32
+                                / 2.,
+                            (merged_grid.top - merged_grid.bottom + 1) as f32 * grid_size as f32
+                                / 2.,
+                        ),
+                        GravityScale(0.0),
+                        RigidBody::Fixed,
+                        TransformBundle::from(Transform::from_xyz(
+                            (merged_grid.left + merged_grid.right + 1) as f32 * grid_size as f32
+                                / 2.,
+                            (merged_grid.bottom + merged_grid.top + 1) as f32 * grid_size as f32
+                                / 2.,
+                            0.,
+                        )),
+                    ));
+
+                    if activated {
+                        child_entity.insert(ActivatedCheckPoint);
+                    }
+
+                    let child_entity = child_entity.id();
                     commands.entity(level_entity).add_child(child_entity);
                 }
             }
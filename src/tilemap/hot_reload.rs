@@ -0,0 +1,122 @@
+//! Respawn the levels when the LDtk project changes on disk
+//!
+//! Walls, check points and mobs are children of their level, so respawning the world rebuilds all
+//! of them from the new project. The player is respawned as well, `spawn_player` puts it back
+//! where it was with the health it had.
+use bevy::prelude::*;
+
+use super::{check_point::LastCheckPoint, LdtkAsset, Respawn};
+use crate::{
+    player::{Direction, Health, Player},
+    state::{AppLooplessStateExt, AppState, ConditionSet},
+};
+
+/// The player as it was when it last changed
+///
+/// bevy_ecs_ldtk may despawn the player before `reload_levels` sees the change of the project, so
+/// it's taken beforehand.
+#[derive(Clone, Debug, Resource)]
+pub(crate) struct PlayerSnapshot {
+    pub(crate) transform: Transform,
+    pub(crate) health: Health,
+    pub(crate) facing_direction: Direction,
+    pub(crate) last_check_point: Option<LastCheckPoint>,
+}
+
+impl PlayerSnapshot {
+    fn update(
+        mut cmd: Commands,
+        player_snapshot: Option<ResMut<PlayerSnapshot>>,
+        players: Query<
+            (&Transform, &Health, &Player, Option<&LastCheckPoint>),
+            Or<(
+                Changed<Transform>,
+                Changed<Health>,
+                Changed<Player>,
+                Changed<LastCheckPoint>,
+            )>,
+        >,
+    ) {
+        if let Ok((transform, health, player, last_check_point)) = players.get_single() {
+            let snapshot = PlayerSnapshot {
+                transform: *transform,
+                health: *health,
+                facing_direction: player.facing_direction,
+                last_check_point: last_check_point.cloned(),
+            };
+
+            // only the first one goes through commands
+            match player_snapshot {
+                Some(mut player_snapshot) => *player_snapshot = snapshot,
+                None => cmd.insert_resource(snapshot),
+            }
+        }
+    }
+}
+
+/// Taken by `spawn_player` when the player is respawned after a reload
+#[derive(Debug, Deref, Resource)]
+pub(crate) struct ReloadedPlayer(pub(crate) PlayerSnapshot);
+
+fn reload_levels(
+    mut cmd: Commands,
+    mut ldtk_events: EventReader<AssetEvent<LdtkAsset>>,
+    worlds: Query<(Entity, &Handle<LdtkAsset>)>,
+    player_snapshot: Option<Res<PlayerSnapshot>>,
+) {
+    for event in ldtk_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            info!("LDtk project changed, respawning the levels");
+
+            // the player may be gone already if bevy_ecs_ldtk has started respawning
+            if let Some(player_snapshot) = &player_snapshot {
+                cmd.insert_resource(ReloadedPlayer(PlayerSnapshot::clone(player_snapshot)));
+            }
+
+            for (world, world_handle) in worlds.iter() {
+                if world_handle == handle {
+                    cmd.entity(world).insert(Respawn);
+                }
+            }
+        }
+    }
+}
+
+fn clean_up(mut cmd: Commands) {
+    cmd.remove_resource::<PlayerSnapshot>();
+    cmd.remove_resource::<ReloadedPlayer>();
+}
+
+pub(crate) struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            ConditionSet::new()
+                .run_in_state(AppState::InGame)
+                .with_system(PlayerSnapshot::update)
+                .with_system(reload_levels)
+                .into(),
+        )
+        .add_exit_system(AppState::InGame, clean_up);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn snapshot_follows_the_player() {
+        let mut app = TestApp::new();
+        app.add_plugin(HotReloadPlugin);
+        let player = app.spawn_player(Vec2::new(0., 24.), 3);
+        app.step();
+
+        app.world.get_mut::<Health>(player).unwrap().current = 1;
+        app.step();
+
+        assert_eq!(app.world.resource::<PlayerSnapshot>().health.current, 1);
+    }
+}
@@ -0,0 +1,159 @@
+//! Assets which only some levels need
+//!
+//! A level lists the keys of `dynamic_asset.assets` it depends on in its `Assets` field, an array
+//! of strings. They are loaded when the level is spawned, which includes the neighbours of the
+//! current level, and released once no spawned level lists them anymore.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::{StandardDynamicAsset, StandardDynamicAssetCollection};
+
+use super::{ldtk, FieldValue, LdtkAsset, LevelEvent};
+use crate::state::{AppLooplessStateExt, AppState, ConditionSet};
+
+const ASSETS_FIELD: &str = "Assets";
+
+/// Handles of the assets of the spawned levels, by level iid then by key
+#[derive(Debug, Default, Resource)]
+pub(crate) struct LevelAssets(HashMap<String, HashMap<String, HandleUntyped>>);
+
+impl LevelAssets {
+    fn load(
+        mut level_events: EventReader<LevelEvent>,
+        mut level_assets: ResMut<LevelAssets>,
+        asset_server: Res<AssetServer>,
+        ldtk_assets: Res<Assets<LdtkAsset>>,
+        manifests: Res<Assets<StandardDynamicAssetCollection>>,
+    ) {
+        for event in level_events.iter() {
+            match event {
+                LevelEvent::SpawnTriggered(iid) => {
+                    if let Some(level) = find_level(iid, &ldtk_assets) {
+                        let assets = asset_keys(level)
+                            .filter_map(|key| match manifest_path(key, &manifests) {
+                                Some(path) => {
+                                    Some((key.to_string(), asset_server.load_untyped(path)))
+                                }
+                                None => {
+                                    warn!(
+                                        "level {} asks for unknown asset {key}",
+                                        level.identifier
+                                    );
+                                    None
+                                }
+                            })
+                            .collect();
+
+                        level_assets.0.insert(iid.clone(), assets);
+                    }
+                }
+                // the handles of the other levels keep their shared assets alive
+                LevelEvent::Despawned(iid) => {
+                    level_assets.0.remove(iid);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn release(mut level_assets: ResMut<LevelAssets>) {
+        level_assets.0.clear();
+    }
+}
+
+fn find_level<'a>(iid: &str, ldtk_assets: &'a Assets<LdtkAsset>) -> Option<&'a ldtk::Level> {
+    ldtk_assets
+        .iter()
+        .find_map(|(_, ldtk_asset)| ldtk_asset.project.levels.iter().find(|l| l.iid == iid))
+}
+
+fn asset_keys(level: &ldtk::Level) -> impl Iterator<Item = &str> {
+    level
+        .field_instances
+        .iter()
+        .filter(|field| field.identifier == ASSETS_FIELD)
+        .flat_map(|field| match &field.value {
+            FieldValue::Strings(keys) => keys.as_slice(),
+            _ => {
+                warn!(
+                    "{ASSETS_FIELD} of level {} isn't an array of strings",
+                    level.identifier
+                );
+                &[][..]
+            }
+        })
+        .flatten()
+        .map(String::as_str)
+}
+
+/// Path of the file a key of the manifest points to
+pub(crate) fn manifest_path<'a>(
+    key: &str,
+    manifests: &'a Assets<StandardDynamicAssetCollection>,
+) -> Option<&'a str> {
+    manifests
+        .iter()
+        .find_map(|(_, manifest)| manifest.0.get(key))
+        .and_then(|asset| match asset {
+            StandardDynamicAsset::File { path } => Some(path.as_str()),
+            _ => None,
+        })
+}
+
+pub(crate) struct LevelAssetsPlugin;
+
+impl Plugin for LevelAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelAssets>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .with_system(LevelAssets::load)
+                    .into(),
+            )
+            .add_exit_system(AppState::InGame, LevelAssets::release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TestApp, tilemap::ldtk::FieldInstance};
+
+    const LEVEL_IID: &str = "test-level-0";
+
+    fn send(app: &mut TestApp, event: LevelEvent) {
+        app.world.resource_mut::<Events<LevelEvent>>().send(event);
+        app.step();
+    }
+
+    #[test]
+    fn spawned_levels_hold_their_assets() {
+        let mut app = TestApp::new();
+        let _manifest = app.add_manifest(&[("level.music.town", "audio/TownTheme.mp3")]);
+        app.spawn_level(
+            0,
+            Vec2::new(0., 0.),
+            Vec2::new(320., 160.),
+            vec![FieldInstance {
+                identifier: ASSETS_FIELD.to_string(),
+                tile: None,
+                field_instance_type: "Array<String>".to_string(),
+                value: FieldValue::Strings(vec![Some("level.music.town".to_string())]),
+                def_uid: 0,
+                real_editor_values: Vec::new(),
+            }],
+        );
+
+        send(&mut app, LevelEvent::SpawnTriggered(LEVEL_IID.to_string()));
+        let handle = app.world.resource::<LevelAssets>().0[LEVEL_IID]["level.music.town"].clone();
+        let expected = app
+            .world
+            .resource::<AssetServer>()
+            .get_handle_untyped("audio/TownTheme.mp3");
+        assert_eq!(handle, expected);
+
+        send(&mut app, LevelEvent::Despawned(LEVEL_IID.to_string()));
+        assert!(app.world.resource::<LevelAssets>().0.is_empty());
+    }
+}
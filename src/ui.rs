@@ -1,8 +1,12 @@
 pub(crate) mod audio;
 pub(crate) mod control;
+pub(crate) mod hud;
 pub(crate) mod menu;
+pub(crate) mod navigation;
 pub(crate) mod options;
 pub(crate) mod save;
+pub(crate) mod save_prompt;
+pub(crate) mod transition;
 
 use bevy::prelude::*;
 
@@ -15,24 +19,47 @@ use move_vis::make_slider;
 
 #[cfg(feature = "debug")]
 use crate::{
-    physics::{set_gravity, set_jump_power_coefficient, RapierConfiguration},
+    physics::{set_gravity, set_jump_power_coefficient, PhysicsScale, RapierConfiguration},
     player::PlayerMovementSettings,
+    save::{
+        export::{convert, FileKind},
+        load_with_fallback, CurrentSave, DataDirs,
+    },
+    ui::menu::GameConfig,
 };
 
 #[cfg(feature = "debug")]
 use crate::state::{AppState, ConditionSet};
 
-pub struct UiPlugin;
+pub struct UiPlugin {
+    /// main menu, save menu, pause menu and options
+    pub(crate) menus: bool,
+}
+
+impl Default for UiPlugin {
+    fn default() -> Self {
+        Self { menus: true }
+    }
+}
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(EguiPlugin).add_plugin(menu::MenuPlugin);
+        app.add_plugin(EguiPlugin)
+            .add_plugin(hud::HudPlugin)
+            .add_plugin(menu::GameConfigPlugin)
+            .add_plugin(menu::GameUiPlugin { menus: self.menus })
+            .add_plugin(transition::TransitionPlugin);
+
+        if self.menus {
+            app.add_plugin(menu::MenuPlugin);
+        }
 
         #[cfg(feature = "debug")]
         app.add_system_set(
             ConditionSet::new()
                 .run_in_state(AppState::InGame)
                 .with_system(movement_ui)
+                .with_system(save_ui)
                 .into(),
         );
     }
@@ -43,6 +70,7 @@ fn movement_ui(
     mut egui_context: ResMut<EguiContext>,
     mut player_movement_settings: ResMut<PlayerMovementSettings>,
     mut rapier_config: ResMut<RapierConfiguration>,
+    physics_scale: Res<PhysicsScale>,
 ) {
     egui::Window::new("Physical Properties Tweaking").show(egui_context.ctx_mut(), |ui| {
         let player_movement_settings = &mut *player_movement_settings;
@@ -99,6 +127,76 @@ fn movement_ui(
         ));
 
         set_gravity(&mut rapier_config, player_movement_settings);
-        set_jump_power_coefficient(&rapier_config, &mut *player_movement_settings);
+        set_jump_power_coefficient(
+            &rapier_config,
+            &mut *player_movement_settings,
+            *physics_scale,
+        );
+    });
+}
+
+/// Export the current save and the config next to their files as RON, and import them back
+#[cfg(feature = "debug")]
+fn save_ui(
+    mut egui_context: ResMut<EguiContext>,
+    current_save: Res<CurrentSave>,
+    data_dirs: Res<DataDirs>,
+    mut game_config: ResMut<GameConfig>,
+) {
+    fn report(result: anyhow::Result<()>, action: &str) {
+        match result {
+            Ok(()) => info!("{action} done"),
+            Err(err) => error!("{action} failed: {err:#}"),
+        }
+    }
+
+    egui::Window::new("Save Files").show(egui_context.ctx_mut(), |ui| {
+        if let Some(save_file) = current_save.path(&data_dirs) {
+            let readable = save_file.with_extension("ron");
+
+            ui.label(readable.display().to_string());
+            ui.horizontal(|ui| {
+                if ui.button("Export Save").clicked() {
+                    report(
+                        convert(&save_file, &readable, Some(FileKind::Save)),
+                        "save export",
+                    );
+                }
+                // the imported save is used the next time the slot is loaded
+                if ui.button("Import Save").clicked() {
+                    report(
+                        convert(&readable, &save_file, Some(FileKind::Save)),
+                        "save import",
+                    );
+                }
+            });
+        }
+
+        let config_file = data_dirs.config_path();
+        let readable = config_file.with_extension("ron");
+
+        ui.label(readable.display().to_string());
+        ui.horizontal(|ui| {
+            if ui.button("Export Config").clicked() {
+                report(
+                    convert(&config_file, &readable, Some(FileKind::Config)),
+                    "config export",
+                );
+            }
+            if ui.button("Import Config").clicked() {
+                let imported =
+                    convert(&readable, &config_file, Some(FileKind::Config)).and_then(|_| {
+                        load_with_fallback::<_, GameConfig>(&config_file).map_err(Into::into)
+                    });
+
+                match imported {
+                    Ok((config, _)) => {
+                        *game_config = config;
+                        info!("config import done");
+                    }
+                    Err(err) => error!("config import failed: {err:#}"),
+                }
+            }
+        });
     });
 }
@@ -1,13 +1,16 @@
-use bevy::prelude::*;
+use bevy::{hierarchy::ChildBuilder, prelude::*};
 use global_state::Transient;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     asset::FontAssets,
-    state::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem, MenuState, NextState},
-    ui::menu::{
-        button_interact, get_button_style, select_button, BackButton, ConfigButton, GameConfig,
-        SelectedOption, NORMAL_BUTTON, TEXT_COLOR,
+    state::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem, MenuState},
+    ui::{
+        menu::{
+            button_interact, get_button_style, select_button, BackButton, ConfigButton, GameConfig,
+            SelectedOption, NORMAL_BUTTON, TEXT_COLOR,
+        },
+        navigation::{Navigator, Screen},
     },
 };
 
@@ -25,12 +28,12 @@ impl Plugin for AudioPlugin {
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(MenuState::Audio)
-                    .with_system(BackButton::to_options_menu.run_if(button_interact::<BackButton>))
-                    .with_system(BackButton::on_esc_to_options_menu)
-                    .with_system(AudioButton::show.run_if(button_interact::<AudioButton>))
+                    .with_system(BackButton::back.run_if(button_interact::<BackButton>))
                     .with_system(select_button::<MasterVolume>)
                     .with_system(select_button::<SoundVolume>)
                     .with_system(select_button::<MusicVolume>)
+                    .with_system(select_button::<MusicMuted>)
+                    .with_system(select_button::<SoundMuted>)
                     .into(),
             );
     }
@@ -40,16 +43,26 @@ impl Plugin for AudioPlugin {
 pub(crate) struct AudioButton;
 
 impl AudioButton {
-    fn show(mut cmd: Commands) {
-        cmd.insert_resource(NextState(MenuState::Audio));
+    fn show(mut navigator: Navigator) {
+        navigator.open(Screen::Menu(MenuState::Audio));
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum AudioCategory {
+    Music,
+    Sound,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub(crate) struct AudioConfig {
     pub(crate) master_volume: MasterVolume,
     pub(crate) music_volume: MusicVolume,
     pub(crate) sound_volume: SoundVolume,
+    #[serde(default)]
+    pub(crate) music_muted: MusicMuted,
+    #[serde(default)]
+    pub(crate) sound_muted: SoundMuted,
 }
 
 impl Default for AudioConfig {
@@ -58,6 +71,24 @@ impl Default for AudioConfig {
             master_volume: MasterVolume(0.5),
             music_volume: MusicVolume(0.5),
             sound_volume: SoundVolume(0.5),
+            music_muted: MusicMuted(false),
+            sound_muted: SoundMuted(false),
+        }
+    }
+}
+
+impl AudioConfig {
+    /// The volume a category is played at, the master volume scales every category
+    pub(crate) fn output_volume(&self, category: AudioCategory) -> f64 {
+        let (volume, muted) = match category {
+            AudioCategory::Music => (*self.music_volume, *self.music_muted),
+            AudioCategory::Sound => (*self.sound_volume, *self.sound_muted),
+        };
+
+        if muted {
+            0.
+        } else {
+            *self.master_volume * volume
         }
     }
 }
@@ -89,6 +120,56 @@ impl ConfigButton for SoundVolume {
     }
 }
 
+#[derive(
+    Clone, Copy, Component, Debug, Default, Deref, DerefMut, PartialEq, Deserialize, Serialize,
+)]
+pub(crate) struct MusicMuted(pub(crate) bool);
+
+impl ConfigButton for MusicMuted {
+    fn save(&self, game_config: &mut ResMut<GameConfig>) {
+        game_config.audio.music_muted = *self;
+    }
+}
+
+#[derive(
+    Clone, Copy, Component, Debug, Default, Deref, DerefMut, PartialEq, Deserialize, Serialize,
+)]
+pub(crate) struct SoundMuted(pub(crate) bool);
+
+impl ConfigButton for SoundMuted {
+    fn save(&self, game_config: &mut ResMut<GameConfig>) {
+        game_config.audio.sound_muted = *self;
+    }
+}
+
+fn spawn_mute_buttons<T: Component + Copy + PartialEq>(
+    parent: &mut ChildBuilder,
+    button_text_style: &TextStyle,
+    current: T,
+    muted: fn(bool) -> T,
+) {
+    parent.spawn(TextBundle::from_section("Mute", button_text_style.clone()));
+    for (label, setting) in [("Off", false), ("On", true)] {
+        let button = muted(setting);
+
+        let mut entity = parent.spawn((
+            Name::new(format!("Mute {label}")),
+            button,
+            ButtonBundle {
+                style: get_button_style(),
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            },
+        ));
+        entity.with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+        });
+        if current == button {
+            entity.insert(SelectedOption);
+        }
+    }
+}
+
 fn audio_menu(mut cmd: Commands, game_config: Res<GameConfig>, font_assets: Res<FontAssets>) {
     let audio_config = &game_config.audio;
 
@@ -236,6 +317,12 @@ fn audio_menu(mut cmd: Commands, game_config: Res<GameConfig>, font_assets: Res<
                                 entity.insert(SelectedOption);
                             }
                         }
+                        spawn_mute_buttons(
+                            parent,
+                            &button_text_style,
+                            audio_config.music_muted,
+                            MusicMuted,
+                        );
                     });
                 parent
                     .spawn((
@@ -273,6 +360,12 @@ fn audio_menu(mut cmd: Commands, game_config: Res<GameConfig>, font_assets: Res<
                                 entity.insert(SelectedOption);
                             }
                         }
+                        spawn_mute_buttons(
+                            parent,
+                            &button_text_style,
+                            audio_config.sound_muted,
+                            SoundMuted,
+                        );
                     });
 
                 BackButton::spawn(parent, button_text_style.clone());
@@ -12,9 +12,12 @@ use crate::{
         AppLooplessStateExt, ConditionSet, CurrentState, IntoConditionalSystem, MenuState,
         NextState,
     },
-    ui::menu::{
-        button_interact, despawn, get_button_style, BackButton, GameConfig, GameConfigSaveEvent,
-        NORMAL_BUTTON, TEXT_COLOR,
+    ui::{
+        menu::{
+            button_interact, despawn, get_button_style, BackButton, GameConfig,
+            GameConfigSaveEvent, NORMAL_BUTTON, TEXT_COLOR,
+        },
+        navigation::{Navigator, Screen},
     },
 };
 
@@ -26,8 +29,7 @@ impl Plugin for ControlPlugin {
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(MenuState::Controls)
-                    .with_system(BackButton::to_options_menu.run_if(button_interact::<BackButton>))
-                    .with_system(BackButton::on_esc_to_options_menu)
+                    .with_system(BackButton::back.run_if(button_interact::<BackButton>))
                     .with_system(BindingButton::show_popup.run_if(button_interact::<BindingButton>))
                     .with_system(binding_window_system)
                     .with_system(ResetButton::reset_inputs.run_if(button_interact::<ResetButton>))
@@ -71,6 +73,14 @@ pub(crate) enum BindingState {
     Conflict,
 }
 
+/// Condition true while an input is being bound, escape then belongs to the binding popup
+pub(crate) fn is_binding(
+    active_binding: Option<Res<ActiveBinding>>,
+    binding_state: Res<CurrentState<BindingState>>,
+) -> bool {
+    active_binding.is_some() || binding_state.0 != BindingState::None
+}
+
 #[derive(Clone, Copy, Component, Debug, PartialEq)]
 struct BindingButton(ControlAction, usize);
 
@@ -152,7 +162,7 @@ impl BindingButton {
 }
 
 #[derive(Clone, Copy, Debug, Resource)]
-struct ActiveBinding {
+pub(crate) struct ActiveBinding {
     action: ControlAction,
     index: usize,
     conflict: Option<BindingConflict>,
@@ -615,8 +625,8 @@ fn control_menu(
 #[derive(Component)]
 pub(crate) struct ControlButton;
 impl ControlButton {
-    pub(crate) fn show(mut cmd: Commands) {
-        cmd.insert_resource(NextState(MenuState::Controls));
+    pub(crate) fn show(mut navigator: Navigator) {
+        navigator.open(Screen::Menu(MenuState::Controls));
     }
 }
 
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::FontAssets,
+    save::SaveTasks,
+    state::{AppLooplessStateExt, AppState, ConditionSet},
+    ui::menu::TEXT_COLOR,
+};
+
+/// seconds the saving indicator stays up, so quick saves don't just flicker
+const SAVING_MIN_DISPLAY: f64 = 1.0;
+
+pub(crate) struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppState::InGame, SavingIndicator::spawn)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame)
+                    .with_system(SavingIndicator::update)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Component, Default)]
+struct SavingIndicator {
+    /// elapsed seconds when a save was last seen in progress
+    last_saving: Option<f64>,
+}
+
+impl SavingIndicator {
+    fn spawn(mut cmd: Commands, font_assets: Res<FontAssets>) {
+        cmd.spawn((
+            Name::new("Saving Indicator"),
+            SavingIndicator::default(),
+            TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(20.0),
+                        bottom: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                text: Text::from_section(
+                    "saving…",
+                    TextStyle {
+                        font: font_assets.monogram.clone(),
+                        font_size: 30.0,
+                        color: TEXT_COLOR,
+                    },
+                ),
+                visibility: Visibility::INVISIBLE,
+                ..default()
+            },
+        ));
+    }
+
+    fn update(
+        time: Res<Time>,
+        save_tasks: Res<SaveTasks>,
+        mut indicators: Query<(&mut SavingIndicator, &mut Visibility)>,
+    ) {
+        let now = time.elapsed_seconds_f64();
+
+        for (mut indicator, mut visibility) in indicators.iter_mut() {
+            if save_tasks.is_saving() {
+                indicator.last_saving = Some(now);
+            }
+
+            visibility.is_visible = indicator
+                .last_saving
+                .map_or(false, |last_saving| now - last_saving < SAVING_MIN_DISPLAY);
+        }
+    }
+}
@@ -1,26 +1,31 @@
-use std::{fs, path::Path};
+use std::fs;
 
-use bevy::{
-    app::AppExit, hierarchy::ChildBuilder, prelude::*, tasks::IoTaskPool, window::close_on_esc,
-};
-use global_state::Transient;
+use bevy::{app::AppExit, hierarchy::ChildBuilder, prelude::*, window::close_on_esc};
+use global_state::StateScoped;
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     asset::FontAssets,
+    audio::sfx::{PlaySfx, Sfx},
     input::{UiAction, UiActionState},
-    physics::{pause_physics, resume_physics, RapierConfiguration},
-    save::{load_file, save_file},
+    physics::{self, RapierConfiguration},
+    save::{
+        autosave::AutosaveConfig, decode, load_with_fallback, write_with_backups, DataDirs,
+        FileError, SaveTarget, SaveTasks, Versioned,
+    },
     state::{
         AppLooplessStateExt, AppState, ConditionSet, CurrentState, IntoConditionalSystem,
         MenuState, NextState, PauseState,
     },
     ui::{
         audio::AudioConfig,
-        control::{BindingState, ControlConfig},
+        control::ControlConfig,
+        navigation::{NavigationPlugin, Navigator, Screen},
         options::OptionPlugin,
         save::SaveMenuPlugin,
+        save_prompt::SavePromptPlugin,
+        transition::{TransitionCommandsExt, TransitionEffect},
     },
 };
 
@@ -31,9 +36,6 @@ pub(crate) const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
 pub(crate) const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
 
-const CONFIG_DIR: &str = "data";
-const CONFIG_FILENAME: &str = "config.bin";
-
 pub(crate) fn get_button_style() -> Style {
     Style {
         size: Size::new(Val::Px(250.0), Val::Px(65.0)),
@@ -48,8 +50,8 @@ pub(crate) fn get_button_style() -> Style {
 struct OptionsButton;
 
 impl OptionsButton {
-    fn show(mut cmd: Commands) {
-        cmd.insert_resource(NextState(MenuState::Options));
+    fn show(mut navigator: Navigator) {
+        navigator.open(Screen::Menu(MenuState::Options));
     }
 }
 
@@ -86,53 +88,53 @@ struct StartGameButton;
 
 impl StartGameButton {
     fn start(mut cmd: Commands) {
-        cmd.insert_resource(NextState(AppState::SaveMenu));
+        cmd.transition_to(AppState::SaveMenu, TransitionEffect::Wipe);
     }
 }
 
 #[derive(Component)]
 struct ResumeButton;
 impl ResumeButton {
-    fn resume(mut rapier_config: ResMut<RapierConfiguration>, mut cmd: Commands) {
-        resume_physics(&mut rapier_config);
-        cmd.insert_resource(NextState(PauseState::Off));
+    /// the pause menu was opened from the game
+    fn resume(mut navigator: Navigator) {
+        navigator.back();
     }
 }
 
 #[derive(Component)]
 struct OptionsMenuButton;
 
+#[derive(Component)]
+struct PauseMenu;
+
+impl PauseMenu {
+    /// menus opened from the pause menu are drawn in its place
+    fn hide_behind_menus(
+        menu_state: Res<CurrentState<MenuState>>,
+        mut pause_menus: Query<&mut Visibility, With<PauseMenu>>,
+    ) {
+        for mut visibility in pause_menus.iter_mut() {
+            let is_visible = menu_state.0 == MenuState::None;
+            if visibility.is_visible != is_visible {
+                visibility.is_visible = is_visible;
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 pub(crate) struct MainMenuButton;
 impl MainMenuButton {
     pub(crate) fn back_to_main_menu(mut cmd: Commands) {
-        cmd.insert_resource(NextState(AppState::MainMenu));
+        cmd.transition_to(AppState::MainMenu, TransitionEffect::Fade);
     }
 }
 
 #[derive(Component)]
 pub(crate) struct BackButton;
 impl BackButton {
-    pub(crate) fn to_main_menu(mut cmd: Commands) {
-        cmd.insert_resource(NextState(MenuState::None));
-    }
-
-    pub(crate) fn to_options_menu(mut cmd: Commands) {
-        cmd.insert_resource(NextState(MenuState::Options));
-    }
-
-    pub(crate) fn on_esc_to_options_menu(
-        mut cmd: Commands,
-        keys: Res<Input<KeyCode>>,
-        menu_state: Res<CurrentState<MenuState>>,
-        binding_state: Res<CurrentState<BindingState>>,
-    ) {
-        if keys.just_pressed(KeyCode::Escape)
-            && menu_state.0 != MenuState::Options
-            && binding_state.0 == BindingState::None
-        {
-            cmd.insert_resource(NextState(MenuState::Options));
-        }
+    pub(crate) fn back(mut navigator: Navigator) {
+        navigator.back();
     }
 
     pub(crate) fn spawn(parent: &mut ChildBuilder, button_text_style: TextStyle) {
@@ -178,13 +180,26 @@ pub(crate) struct SelectedOption;
 pub(crate) struct GameConfig {
     pub(crate) audio: AudioConfig,
     pub(crate) control: ControlConfig,
+    #[serde(default)]
+    pub(crate) autosave: AutosaveConfig,
+}
+
+impl Versioned for GameConfig {
+    const VERSION: u32 = 3;
+
+    fn migrate(version: u32, payload: &[u8]) -> Result<Self, FileError> {
+        match version {
+            // config files written before the envelope share the layout of version 1, which only
+            // lacks the trailing `autosave`, version 2 lacks the mute flags of `audio`
+            0 | 1 | 2 => decode(payload),
+            _ => Err(FileError::UnknownVersion(version)),
+        }
+    }
 }
 
 impl GameConfig {
-    fn load(mut cmd: Commands) {
-        if let Ok(config) =
-            load_file::<_, GameConfig>(Path::new(&format!("{CONFIG_DIR}/{CONFIG_FILENAME}")), 0)
-        {
+    fn load(mut cmd: Commands, data_dirs: Res<DataDirs>) {
+        if let Ok((config, _)) = load_with_fallback::<_, GameConfig>(data_dirs.config_path()) {
             info!("loaded save data {:?}", &config);
             cmd.insert_resource(config);
         } else {
@@ -192,48 +207,72 @@ impl GameConfig {
         }
     }
 
-    fn save(game_config: Res<GameConfig>) {
+    fn save(
+        game_config: Res<GameConfig>,
+        data_dirs: Res<DataDirs>,
+        mut save_tasks: ResMut<SaveTasks>,
+    ) {
         let game_config = game_config.clone();
+        let config_dir = data_dirs.config.clone();
+        let config_file = data_dirs.config_path();
 
-        IoTaskPool::get()
-            .spawn(async move {
-                let path = format!("{CONFIG_DIR}/{CONFIG_FILENAME}");
-                let config_file = Path::new(&path);
-                info!("saving {:?} to {:?}", game_config, config_file);
-                fs::create_dir_all(CONFIG_DIR).expect("Error while creating the *data* folder");
-
-                let tmp_file = format!("{}_tmp", config_file.display());
+        save_tasks.spawn(SaveTarget::Config, move || {
+            info!("saving {:?} to {:?}", game_config, config_file);
+            fs::create_dir_all(config_dir)?;
 
-                save_file(&tmp_file, 0, &game_config).expect("Error while saving config file");
-
-                if fs::rename(tmp_file, config_file).is_err() {
-                    error!("cannot rename tmp config file");
-                }
-            })
-            .detach();
+            write_with_backups(config_file, &(), &game_config)
+        });
     }
 }
 
 pub(crate) struct GameConfigSaveEvent;
 
-pub(crate) struct MenuPlugin;
+/// Loads and saves `GameConfig`, which the game needs with or without menus
+pub(crate) struct GameConfigPlugin;
 
-impl Plugin for MenuPlugin {
+impl Plugin for GameConfigPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameConfig>()
             .add_event::<GameConfigSaveEvent>()
-            .add_plugin(OptionPlugin)
-            .add_plugin(SaveMenuPlugin)
             .add_startup_system(GameConfig::load)
+            .add_system(GameConfig::save.run_on_event::<GameConfigSaveEvent>());
+    }
+}
+
+/// Pausing and the save prompt, which the game needs with or without menus
+pub(crate) struct GameUiPlugin {
+    pub(crate) menus: bool,
+}
+
+impl Plugin for GameUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(SavePromptPlugin)
             .add_system(button_interact_visual)
-            .add_system(GameConfig::save.run_on_event::<GameConfigSaveEvent>())
-            .add_enter_system(AppState::MainMenu, setup_menu)
             .add_enter_system(AppState::InGame, init)
             .add_exit_system(AppState::InGame, clean_up)
+            .add_enter_system(PauseState::On, pause_physics)
+            .add_exit_system(PauseState::On, resume_physics);
+
+        // the pause menu resumes the game otherwise
+        if !self.menus {
+            app.add_system(toggle_pause.run_not_in_state(PauseState::None));
+        }
+    }
+}
+
+pub(crate) struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(NavigationPlugin)
+            .add_plugin(OptionPlugin)
+            .add_plugin(SaveMenuPlugin)
+            .add_enter_system(AppState::MainMenu, setup_menu)
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(AppState::MainMenu)
-                    .with_system(close_on_esc)
+                    // escape closes the other menus first
+                    .with_system(close_on_esc.run_in_state(MenuState::None))
                     .with_system(QuitButton::exit.run_if(button_interact::<QuitButton>))
                     .with_system(StartGameButton::start.run_if(button_interact::<StartGameButton>))
                     .with_system(OptionsButton::show.run_if(button_interact::<OptionsButton>))
@@ -254,11 +293,9 @@ impl Plugin for MenuPlugin {
                     .with_system(
                         MainMenuButton::back_to_main_menu.run_if(button_interact::<MainMenuButton>),
                     )
-                    .with_system(
-                        BackButton::to_options_menu.run_if(button_interact::<OptionsMenuButton>),
-                    )
+                    .with_system(OptionsButton::show.run_if(button_interact::<OptionsMenuButton>))
                     .with_system(QuitButton::exit.run_if(button_interact::<QuitButton>))
-                    .with_system(ResumeButton::resume.run_if(on_esc_pause_menu))
+                    .with_system(PauseMenu::hide_behind_menus)
                     .into(),
             );
         // .add_enter_system(MenuState::None, despawn::<Despawnable>)
@@ -369,8 +406,13 @@ pub(crate) fn button_interact_visual(
         (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
         (Changed<Interaction>, With<Button>),
     >,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
     for (interaction, mut color, selected) in query.iter_mut() {
+        if *interaction == Interaction::Clicked {
+            sfx_events.send(PlaySfx(Sfx::Click));
+        }
+
         *color = match (*interaction, selected) {
             (Interaction::Clicked, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
             (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
@@ -408,7 +450,8 @@ fn pause_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
 
     cmd.spawn((
         Name::new("Pause Menu"),
-        Transient,
+        PauseMenu,
+        StateScoped::new(PauseState::On),
         NodeBundle {
             style: Style {
                 margin: UiRect::all(Val::Auto),
@@ -487,23 +530,35 @@ pub(crate) fn on_esc_main_menu(
     }
 }
 
-/// Pressing escape in PauseState::On hides pause menu
-fn on_esc_pause_menu(keys: Res<Input<KeyCode>>) -> bool {
-    keys.just_pressed(KeyCode::Escape)
+fn pause(mut navigator: Navigator, input: Query<&UiActionState>) {
+    let input = input.single();
+    if input.just_pressed(UiAction::Pause) {
+        navigator.open(Screen::Pause(PauseState::On));
+    }
 }
 
-fn pause(
-    mut rapier_config: ResMut<RapierConfiguration>,
+fn toggle_pause(
     mut cmd: Commands,
     input: Query<&UiActionState>,
+    pause_state: Res<CurrentState<PauseState>>,
 ) {
-    let input = input.single();
-    if input.just_pressed(UiAction::Pause) {
-        pause_physics(&mut rapier_config);
-        cmd.insert_resource(NextState(PauseState::On));
+    if input.single().just_pressed(UiAction::Pause) {
+        let next = match pause_state.0 {
+            PauseState::On => PauseState::Off,
+            _ => PauseState::On,
+        };
+        cmd.insert_resource(NextState(next));
     }
 }
 
+fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    physics::pause_physics(&mut rapier_config);
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    physics::resume_physics(&mut rapier_config);
+}
+
 pub(crate) fn despawn<T: Component>(mut cmd: Commands, query: Query<Entity, With<T>>) {
     for entity in query.iter() {
         cmd.entity(entity).despawn_recursive();
@@ -0,0 +1,109 @@
+//! Menus opened on top of each other
+//!
+//! Opening a screen remembers the one it replaces, so going back returns to whatever opened the
+//! current screen: the options opened from the pause menu return to the pause menu, the ones opened
+//! from the main menu return to the main menu.
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    state::{AppState, ConditionSet, CurrentState, MenuState, NextState, PauseState},
+    ui::control::is_binding,
+};
+
+/// A screen reachable through the menus, each kind of screen is driven by its own state
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Screen {
+    Menu(MenuState),
+    Pause(PauseState),
+}
+
+impl Screen {
+    fn show(self, cmd: &mut Commands) {
+        match self {
+            Self::Menu(state) => cmd.insert_resource(NextState(state)),
+            Self::Pause(state) => cmd.insert_resource(NextState(state)),
+        }
+    }
+}
+
+/// Screens to go back to, the last one was replaced by the current screen
+#[derive(Debug, Default, Resource)]
+pub(crate) struct Navigation {
+    stack: Vec<Screen>,
+}
+
+impl Navigation {
+    /// the stack only makes sense within the `AppState` it was built in
+    fn clear_on_app_state_change(
+        mut navigation: ResMut<Navigation>,
+        app_state: Res<CurrentState<AppState>>,
+    ) {
+        if app_state.is_changed() {
+            navigation.stack.clear();
+        }
+    }
+}
+
+/// Opens screens and goes back to the previous ones
+#[derive(SystemParam)]
+pub(crate) struct Navigator<'w, 's> {
+    cmd: Commands<'w, 's>,
+    navigation: ResMut<'w, Navigation>,
+    menu_state: Res<'w, CurrentState<MenuState>>,
+    pause_state: Res<'w, CurrentState<PauseState>>,
+}
+
+impl Navigator<'_, '_> {
+    fn current(&self, screen: Screen) -> Screen {
+        match screen {
+            Screen::Menu(_) => Screen::Menu(self.menu_state.0),
+            Screen::Pause(_) => Screen::Pause(self.pause_state.0),
+        }
+    }
+
+    /// Show `screen`, going back returns to the screen of the same kind it replaces
+    pub(crate) fn open(&mut self, screen: Screen) {
+        let current = self.current(screen);
+
+        // opening the current screen again only refreshes it
+        if current != screen {
+            self.navigation.stack.push(current);
+        }
+
+        screen.show(&mut self.cmd);
+    }
+
+    /// Return to the screen which opened the current one, false when there is none
+    pub(crate) fn back(&mut self) -> bool {
+        match self.navigation.stack.pop() {
+            Some(screen) => {
+                screen.show(&mut self.cmd);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn back_on_esc(mut navigator: Navigator, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        navigator.back();
+    }
+}
+
+pub(crate) struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Navigation>()
+            .add_system(Navigation::clear_on_app_state_change)
+            .add_system_set(
+                // escape opens the pause menu while playing
+                ConditionSet::new()
+                    .run_not_in_state(PauseState::Off)
+                    .run_if_not(is_binding)
+                    .with_system(back_on_esc)
+                    .into(),
+            );
+    }
+}
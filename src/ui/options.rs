@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use global_state::Transient;
+use global_state::StateScoped;
 
 use crate::{
     asset::FontAssets,
@@ -7,10 +7,7 @@ use crate::{
     ui::{
         audio::{AudioButton, AudioPlugin},
         control::{ControlButton, ControlPlugin},
-        menu::{
-            button_interact, get_button_style, on_esc_main_menu, BackButton, NORMAL_BUTTON,
-            TEXT_COLOR,
-        },
+        menu::{button_interact, get_button_style, BackButton, NORMAL_BUTTON, TEXT_COLOR},
     },
 };
 
@@ -24,8 +21,7 @@ impl Plugin for OptionPlugin {
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(MenuState::Options)
-                    .with_system(BackButton::to_main_menu.run_if(button_interact::<BackButton>))
-                    .with_system(on_esc_main_menu)
+                    .with_system(BackButton::back.run_if(button_interact::<BackButton>))
                     .into(),
             );
     }
@@ -44,7 +40,7 @@ fn options_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
 
     cmd.spawn((
         Name::new("Options Menu"),
-        Transient,
+        StateScoped::new(MenuState::Options),
         NodeBundle {
             style: Style {
                 margin: UiRect::all(Val::Auto),
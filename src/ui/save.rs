@@ -1,13 +1,17 @@
+use std::mem;
+
 use bevy::prelude::*;
-use std::fs;
 
 use crate::{
     asset::FontAssets,
-    save::{load_file, CurrentSave, Save, SaveData, SaveSlots},
+    save::{CurrentSave, DataDirs, Save, SaveSlots, SaveStatus},
     state::{AppLooplessStateExt, AppState, ConditionSet, IntoConditionalSystem, NextState},
-    ui::menu::{
-        button_interact, get_button_style, on_esc_main_menu, BackButton, MainMenuButton,
-        NORMAL_BUTTON, TEXT_COLOR,
+    ui::{
+        menu::{
+            button_interact, get_button_style, on_esc_main_menu, BackButton, MainMenuButton,
+            SelectedOption, NORMAL_BUTTON, PRESSED_BUTTON, TEXT_COLOR,
+        },
+        transition::{TransitionCommandsExt, TransitionEffect},
     },
 };
 
@@ -16,18 +20,17 @@ pub(crate) struct SaveMenuPlugin;
 impl Plugin for SaveMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_enter_system(AppState::SaveMenu, save_menu)
+            .add_exit_system(AppState::SaveMenu, RenameSlot::cancel)
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(AppState::SaveMenu)
-                    .with_system(NewSaveButton::create.run_if(button_interact::<NewSaveButton>))
-                    .with_system(LoadSaveButton::load.run_if(button_interact::<LoadSaveButton>))
+                    .with_system(SlotButton::click.run_if(button_interact::<SlotButton>))
                     .with_system(
                         MainMenuButton::back_to_main_menu.run_if(button_interact::<BackButton>),
                     )
                     .with_system(DeleteButton::delete.run_if(button_interact::<DeleteButton>))
-                    .with_system(
-                        DeleteModeButton::toggle.run_if(button_interact::<DeleteModeButton>),
-                    )
+                    .with_system(ModeButton::toggle.run_if(button_interact::<ModeButton>))
+                    .with_system(RenameSlot::input)
                     .with_system(update_menu)
                     .with_system(on_esc_main_menu)
                     .into(),
@@ -35,62 +38,212 @@ impl Plugin for SaveMenuPlugin {
     }
 }
 
-#[derive(Component)]
-struct NewSaveButton;
-impl NewSaveButton {
-    fn create(mut cmd: Commands, mut current_save: ResMut<CurrentSave>) {
-        cmd.insert_resource(NextState(AppState::InGameAssetLoading));
-        current_save.0 = SaveSlots::new_save();
+/// What clicking on a save slot does
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq)]
+enum SaveMenuMode {
+    #[default]
+    Play,
+    Delete,
+    /// holds the source slot once it is picked
+    Copy(Option<usize>),
+    Rename,
+}
+
+impl SaveMenuMode {
+    fn is_same_kind(&self, other: &Self) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Play => "Play",
+            Self::Delete => "Delete Save",
+            Self::Copy(_) => "Copy Save",
+            Self::Rename => "Rename Save",
+        }
     }
 }
 
 #[derive(Component, Debug, Deref, DerefMut)]
-struct LoadSaveButton(Save);
-impl LoadSaveButton {
-    fn load(
+struct SlotButton(Save);
+
+impl SlotButton {
+    fn click(
         mut cmd: Commands,
-        query: Query<(&Interaction, &LoadSaveButton), (Changed<Interaction>, With<Button>)>,
+        query: Query<(&Interaction, &SlotButton), (Changed<Interaction>, With<Button>)>,
+        mut mode: Query<&mut SaveMenuMode>,
         mut current_save: ResMut<CurrentSave>,
+        data_dirs: Res<DataDirs>,
+        font_assets: Res<FontAssets>,
     ) {
+        let mut mode = mode.single_mut();
+
         for (interaction, button) in query.iter() {
-            if *interaction == Interaction::Clicked {
-                current_save.0 = button.0.clone();
-                debug!("use save {:?}", &current_save);
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
 
-                if let Some(path) = &current_save.0.path {
-                    let save_data: SaveData = load_file(path, 0).unwrap();
-                    debug!("loaded save data {:?}", &save_data);
-                    current_save.0.data = Some(save_data);
-                }
+            let slot = button.slot.expect("save menu only lists slots");
 
-                cmd.insert_resource(NextState(AppState::InGameAssetLoading));
+            match *mode {
+                SaveMenuMode::Play => match button.status {
+                    SaveStatus::Damaged => {
+                        warn!("cannot load damaged save {:?}", button.path(&data_dirs));
+                    }
+                    _ => {
+                        current_save.0 = button.0.clone();
+                        debug!("use save {:?}", &current_save);
+
+                        cmd.transition_to(AppState::InGameAssetLoading, TransitionEffect::Iris);
+                    }
+                },
+                SaveMenuMode::Delete => (),
+                SaveMenuMode::Copy(None) => {
+                    if button.data.is_some() {
+                        *mode = SaveMenuMode::Copy(Some(slot));
+                    }
+                }
+                SaveMenuMode::Copy(Some(from)) if from == slot => {
+                    *mode = SaveMenuMode::Copy(None);
+                }
+                SaveMenuMode::Copy(Some(from)) => {
+                    if let Err(err) = SaveSlots::copy(&data_dirs, from, slot) {
+                        error!("cannot copy save {from} to {slot}: {err:#}");
+                    }
+                    cmd.insert_resource(NextState(AppState::SaveMenu));
+                }
+                SaveMenuMode::Rename => {
+                    if button.data.is_some() {
+                        RenameSlot::new(slot, button.name()).spawn(&mut cmd, &font_assets);
+                    }
+                }
             }
         }
     }
+
+    fn title(&self) -> String {
+        match self.status {
+            SaveStatus::Empty | SaveStatus::Ok => self.name(),
+            SaveStatus::Restored => format!("{} (restored)", self.name()),
+            SaveStatus::Damaged => format!("{} (damaged)", self.name()),
+        }
+    }
+
+    fn details(&self) -> Vec<String> {
+        match (&self.metadata, self.status) {
+            (_, SaveStatus::Empty) => vec!["Empty".to_string()],
+            (Some(metadata), _) => vec![
+                format!(
+                    "{}  {:.0}% complete",
+                    metadata.level_name, metadata.completion
+                ),
+                format!(
+                    "Played {}  Deaths {}",
+                    metadata.playtime_label(),
+                    metadata.deaths
+                ),
+                format!("Saved {}", metadata.saved_at_label()),
+            ],
+            (None, _) => vec![],
+        }
+    }
+
+    fn spawn_card(
+        self,
+        parent: &mut ChildBuilder,
+        title_text_style: TextStyle,
+        detail_text_style: TextStyle,
+    ) {
+        let title = self.title();
+        let details = self.details();
+
+        parent
+            .spawn((
+                Name::new("Save Card"),
+                ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(420.0), Val::Px(110.0)),
+                        margin: UiRect::all(Val::Px(8.0)),
+                        flex_direction: FlexDirection::Column,
+                        ..get_button_style()
+                    },
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                },
+                self,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(title, title_text_style));
+
+                for detail in details {
+                    parent.spawn(TextBundle::from_section(detail, detail_text_style.clone()));
+                }
+            });
+    }
 }
 
-#[derive(Component, Debug)]
-struct DeleteMode(bool);
+#[derive(Component)]
+struct ModeButton(SaveMenuMode);
+
+impl ModeButton {
+    fn toggle(
+        query: Query<(&Interaction, &ModeButton), (Changed<Interaction>, With<Button>)>,
+        mut mode: Query<&mut SaveMenuMode>,
+    ) {
+        let mut mode = mode.single_mut();
+
+        for (interaction, ModeButton(button_mode)) in query.iter() {
+            if *interaction == Interaction::Clicked {
+                *mode = if mode.is_same_kind(button_mode) {
+                    SaveMenuMode::Play
+                } else {
+                    *button_mode
+                };
+            }
+        }
+    }
+
+    fn spawn(parent: &mut ChildBuilder, mode: SaveMenuMode, text_style: TextStyle) {
+        parent
+            .spawn((
+                Name::new(mode.label()),
+                ModeButton(mode),
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_sections([TextSection::new(mode.label(), text_style)]),
+                    ModeButtonText(mode),
+                ));
+            });
+    }
+}
 
 #[derive(Component)]
-struct DeleteModeButtonText;
+struct ModeButtonText(SaveMenuMode);
 
 #[derive(Component)]
-struct DeleteButton(Save);
+struct DeleteButton(usize);
 
 impl DeleteButton {
     fn delete(
         mut cmd: Commands,
         query: Query<(&Interaction, &DeleteButton), (Changed<Interaction>, With<Button>)>,
+        data_dirs: Res<DataDirs>,
     ) {
-        for (interaction, button) in query.iter() {
+        for (interaction, DeleteButton(slot)) in query.iter() {
             if *interaction == Interaction::Clicked {
-                if let Some(ref path) = button.0.path {
-                    if fs::remove_file(path).is_ok() {
-                        cmd.insert_resource(NextState(AppState::SaveMenu));
-                    } else {
-                        error!("cannot remove save {:?}", button.0);
-                    }
+                if SaveSlots::delete(&data_dirs, *slot).is_ok() {
+                    cmd.insert_resource(NextState(AppState::SaveMenu));
+                } else {
+                    error!("cannot remove save {slot}");
                 }
             }
         }
@@ -98,86 +251,222 @@ impl DeleteButton {
 }
 
 #[derive(Component)]
-struct DeleteModeButton;
+struct RenamePopUp;
+
+#[derive(Component)]
+struct RenameText;
 
-impl DeleteModeButton {
-    fn toggle(mut delete_mode: Query<&mut DeleteMode>) {
-        if let Ok(mut delete_mode) = delete_mode.get_single_mut() {
-            delete_mode.0 = !delete_mode.0;
+/// Name being typed for a slot
+#[derive(Debug, Resource)]
+struct RenameSlot {
+    slot: usize,
+    name: String,
+}
+
+impl RenameSlot {
+    const MAX_LENGTH: usize = 20;
+
+    fn new(slot: usize, name: String) -> Self {
+        Self { slot, name }
+    }
+
+    fn spawn(self, cmd: &mut Commands, font_assets: &FontAssets) {
+        let text_style = TextStyle {
+            font: font_assets.monogram.clone(),
+            font_size: 40.0,
+            color: TEXT_COLOR,
+        };
+
+        cmd.spawn((
+            Name::new("Rename Popup"),
+            RenamePopUp,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Auto),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::NAVY.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Type a name, Enter to confirm or Esc to cancel",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(self.name.clone(), text_style),
+                        RenameText,
+                    ));
+                });
+        });
+
+        cmd.insert_resource(self);
+    }
+
+    fn input(
+        mut cmd: Commands,
+        rename_slot: Option<ResMut<RenameSlot>>,
+        data_dirs: Res<DataDirs>,
+        mut characters: EventReader<ReceivedCharacter>,
+        keys: Res<Input<KeyCode>>,
+        mut rename_text: Query<&mut Text, With<RenameText>>,
+        popups: Query<Entity, With<RenamePopUp>>,
+    ) {
+        let mut rename_slot = match rename_slot {
+            Some(rename_slot) => rename_slot,
+            None => return,
+        };
+
+        if keys.just_pressed(KeyCode::Escape) {
+            cmd.remove_resource::<RenameSlot>();
+            for popup in popups.iter() {
+                cmd.entity(popup).despawn_recursive();
+            }
+        } else if keys.just_pressed(KeyCode::Return) {
+            let name = rename_slot.name.trim().to_string();
+
+            if let Err(err) = SaveSlots::rename(&data_dirs, rename_slot.slot, name) {
+                error!("cannot rename save {}: {err:#}", rename_slot.slot);
+            }
+            cmd.insert_resource(NextState(AppState::SaveMenu));
+        } else {
+            if keys.just_pressed(KeyCode::Back) {
+                rename_slot.name.pop();
+            }
+
+            for ReceivedCharacter { char, .. } in characters.iter() {
+                if !char.is_control() && rename_slot.name.chars().count() < Self::MAX_LENGTH {
+                    rename_slot.name.push(*char);
+                }
+            }
+
+            if rename_slot.is_changed() {
+                if let Ok(mut text) = rename_text.get_single_mut() {
+                    text.sections[0].value = rename_slot.name.clone();
+                }
+            }
         }
     }
+
+    fn cancel(mut cmd: Commands) {
+        cmd.remove_resource::<RenameSlot>();
+    }
 }
 
 fn update_menu(
     mut cmd: Commands,
-    save_nodes: Query<(&Parent, &LoadSaveButton)>,
-    mut delete_buttons: Query<Entity, With<DeleteButton>>,
-    delete_mode: Query<&DeleteMode, Changed<DeleteMode>>,
-    mut button_text: Query<&mut Text, With<DeleteModeButtonText>>,
+    mode: Query<&SaveMenuMode, Changed<SaveMenuMode>>,
+    mut slot_buttons: Query<(Entity, &Parent, &SlotButton, &mut BackgroundColor)>,
+    delete_buttons: Query<Entity, With<DeleteButton>>,
+    mut mode_texts: Query<(&mut Text, &ModeButtonText)>,
     font_assets: Res<FontAssets>,
 ) {
-    if let Ok(DeleteMode(delete_mode)) = delete_mode.get_single() {
-        if let Ok(mut delete_text) = button_text.get_single_mut() {
-            if *delete_mode {
-                // update Delete Mode Button text
-                delete_text.sections[0].value = "Cancel".to_string();
-
-                // insert delete buttons
-                let button_text_style = TextStyle {
-                    font: font_assets.monogram.clone(),
-                    font_size: 40.0,
-                    color: TEXT_COLOR,
-                };
+    let mode = match mode.get_single() {
+        Ok(mode) => mode,
+        Err(_) => return,
+    };
 
-                for (parent, save_button) in save_nodes.iter() {
-                    let button_id = cmd
-                        .spawn((
-                            Name::new("Delete Button"),
-                            DeleteButton(save_button.0.clone()),
-                            ButtonBundle {
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle {
-                                text: Text::from_section("Delete?", button_text_style.clone()),
-                                ..default()
-                            });
-                        })
-                        .id();
+    // the active mode button becomes a cancel button
+    for (mut text, ModeButtonText(button_mode)) in mode_texts.iter_mut() {
+        text.sections[0].value = if mode.is_same_kind(button_mode) {
+            "Cancel".to_string()
+        } else {
+            button_mode.label().to_string()
+        };
+    }
 
-                    cmd.entity(parent.get()).push_children(&[button_id]);
-                }
-            } else {
-                // update Delete Mode Button text
-                delete_text.sections[0].value = "Delete Save".to_string();
+    // highlight the source of a copy
+    for (entity, _, slot_button, mut color) in slot_buttons.iter_mut() {
+        if *mode == SaveMenuMode::Copy(slot_button.slot) {
+            *color = PRESSED_BUTTON.into();
+            cmd.entity(entity).insert(SelectedOption);
+        } else {
+            *color = NORMAL_BUTTON.into();
+            cmd.entity(entity).remove::<SelectedOption>();
+        }
+    }
 
-                // remove delete buttons
-                for entty in &mut delete_buttons {
-                    cmd.entity(entty).despawn_recursive();
-                }
-            }
+    for entity in delete_buttons.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    if *mode == SaveMenuMode::Delete {
+        let button_text_style = TextStyle {
+            font: font_assets.monogram.clone(),
+            font_size: 40.0,
+            color: TEXT_COLOR,
+        };
+
+        for (_, parent, slot_button, _) in slot_buttons.iter() {
+            let slot = match slot_button.slot {
+                Some(slot) if slot_button.status != SaveStatus::Empty => slot,
+                _ => continue,
+            };
+
+            let button_id = cmd
+                .spawn((
+                    Name::new("Delete Button"),
+                    DeleteButton(slot),
+                    ButtonBundle {
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: Text::from_section("Delete?", button_text_style.clone()),
+                        ..default()
+                    });
+                })
+                .id();
+
+            cmd.entity(parent.get()).push_children(&[button_id]);
         }
     }
 }
 
-fn save_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
+fn save_menu(mut cmd: Commands, data_dirs: Res<DataDirs>, font_assets: Res<FontAssets>) {
     cmd.spawn(Camera2dBundle::default());
 
     let font = font_assets.monogram.clone();
 
-    let button_style = get_button_style();
-
     let button_text_style = TextStyle {
         font: font.clone(),
         font_size: 40.0,
         color: TEXT_COLOR,
     };
 
+    let detail_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+
+    let SaveSlots(saves) = SaveSlots::get_saves(&data_dirs);
+
+    let has_saves = saves.iter().any(|save| save.status != SaveStatus::Empty);
+
     cmd.spawn((
         Name::new("Save Menu"),
-        DeleteMode(false),
+        SaveMenuMode::default(),
         NodeBundle {
             style: Style {
                 margin: UiRect::all(Val::Auto),
@@ -204,7 +493,7 @@ fn save_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
                 },
             ))
             .with_children(|parent| {
-                SaveSlots::new(5).iter().enumerate().for_each(|(i, save)| {
+                saves.into_iter().enumerate().for_each(|(i, save)| {
                     parent
                         .spawn((
                             Name::new(format!("Save Slot {i}")),
@@ -220,41 +509,30 @@ fn save_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
                             },
                         ))
                         .with_children(|parent| {
-                            let mut button = parent.spawn(ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            });
-
-                            if save.filename == "New Save" {
-                                button.insert(NewSaveButton {});
-                            } else {
-                                button.insert(LoadSaveButton(save.clone()));
-                            };
-
-                            button.with_children(|parent| {
-                                parent.spawn(TextBundle {
-                                    text: Text::from_section(
-                                        save.filename.to_owned(),
-                                        button_text_style.clone(),
-                                    ),
-                                    ..default()
-                                });
-                            });
+                            SlotButton(save).spawn_card(
+                                parent,
+                                button_text_style.clone(),
+                                detail_text_style.clone(),
+                            );
                         });
                 });
 
                 BackButton::spawn(parent, button_text_style.clone());
             });
 
-        let SaveSlots(saves) = SaveSlots::get_saves();
+        if has_saves {
+            let action_text_style = TextStyle {
+                font,
+                font_size: 20.0,
+                color: Color::WHITE,
+            };
 
-        if !saves.is_empty() {
             parent
                 .spawn((
                     Name::new("Actions"),
                     NodeBundle {
                         style: Style {
+                            flex_direction: FlexDirection::Column,
                             align_items: AlignItems::FlexStart,
                             ..default()
                         },
@@ -263,27 +541,13 @@ fn save_menu(mut cmd: Commands, font_assets: Res<FontAssets>) {
                     },
                 ))
                 .with_children(|parent| {
-                    parent
-                        .spawn((
-                            Name::new("Delete Save"),
-                            DeleteModeButton,
-                            ButtonBundle {
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                        ))
-                        .with_children(|parent| {
-                            parent
-                                .spawn(TextBundle::from_sections([TextSection::new(
-                                    "Delete Save",
-                                    TextStyle {
-                                        font,
-                                        font_size: 20.0,
-                                        color: Color::WHITE,
-                                    },
-                                )]))
-                                .insert(DeleteModeButtonText);
-                        });
+                    for mode in [
+                        SaveMenuMode::Delete,
+                        SaveMenuMode::Copy(None),
+                        SaveMenuMode::Rename,
+                    ] {
+                        ModeButton::spawn(parent, mode, action_text_style.clone());
+                    }
                 });
         }
     });
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::FontAssets,
+    save::{quick::QuickSaveEvent, SaveError, SaveEvent, SaveTarget},
+    state::IntoConditionalSystem,
+    ui::menu::{
+        button_interact, despawn, get_button_style, GameConfigSaveEvent, NORMAL_BUTTON, TEXT_COLOR,
+    },
+};
+
+/// Tells the player when a save failed and offers to try again
+pub(crate) struct SavePromptPlugin;
+
+impl Plugin for SavePromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(SavePrompt::show)
+            .add_system(RetryButton::retry.run_if(button_interact::<RetryButton>))
+            .add_system(despawn::<SavePrompt>.run_if(button_interact::<DismissButton>));
+    }
+}
+
+#[derive(Component)]
+struct SavePrompt;
+
+impl SavePrompt {
+    fn show(
+        mut cmd: Commands,
+        mut error_events: EventReader<SaveError>,
+        prompts: Query<Entity, With<SavePrompt>>,
+        font_assets: Option<Res<FontAssets>>,
+    ) {
+        let (SaveError { target, error }, font_assets) =
+            match (error_events.iter().last(), font_assets) {
+                (Some(save_error), Some(font_assets)) => (save_error, font_assets),
+                _ => return,
+            };
+
+        // only the latest failure is shown
+        for prompt in prompts.iter() {
+            cmd.entity(prompt).despawn_recursive();
+        }
+
+        let title = match target {
+            SaveTarget::Slot(slot) => format!("Could not save to slot {}", slot + 1),
+            SaveTarget::QuickSave(slot) => format!("Could not quick save slot {}", slot + 1),
+            SaveTarget::Config => "Could not save the settings".to_string(),
+        };
+
+        let button_text_style = TextStyle {
+            font: font_assets.monogram.clone(),
+            font_size: 40.0,
+            color: TEXT_COLOR,
+        };
+
+        let message_text_style = TextStyle {
+            font_size: 20.0,
+            ..button_text_style.clone()
+        };
+
+        cmd.spawn((
+            Name::new("Save Prompt"),
+            SavePrompt,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Auto),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::NAVY.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(title, button_text_style.clone()));
+                    parent.spawn(TextBundle::from_section(
+                        error.to_string(),
+                        message_text_style,
+                    ));
+
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn((
+                                    Name::new("Retry Button"),
+                                    RetryButton(*target),
+                                    ButtonBundle {
+                                        style: get_button_style(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Retry",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            parent
+                                .spawn((
+                                    Name::new("Dismiss Button"),
+                                    DismissButton,
+                                    ButtonBundle {
+                                        style: get_button_style(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Dismiss",
+                                        button_text_style,
+                                    ));
+                                });
+                        });
+                });
+        });
+    }
+}
+
+#[derive(Component)]
+struct RetryButton(SaveTarget);
+
+impl RetryButton {
+    fn retry(
+        mut cmd: Commands,
+        query: Query<(&Interaction, &RetryButton), (Changed<Interaction>, With<Button>)>,
+        prompts: Query<Entity, With<SavePrompt>>,
+        mut save_event: EventWriter<SaveEvent>,
+        mut quick_save_event: EventWriter<QuickSaveEvent>,
+        mut config_save_event: EventWriter<GameConfigSaveEvent>,
+    ) {
+        for (interaction, RetryButton(target)) in query.iter() {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+
+            match target {
+                SaveTarget::Slot(_) => save_event.send(SaveEvent),
+                SaveTarget::QuickSave(_) => quick_save_event.send(QuickSaveEvent),
+                SaveTarget::Config => config_save_event.send(GameConfigSaveEvent),
+            }
+
+            for prompt in prompts.iter() {
+                cmd.entity(prompt).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct DismissButton;
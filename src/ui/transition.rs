@@ -0,0 +1,254 @@
+//! Screen transitions between `AppState`s
+//!
+//! The out half of the effect covers the screen, only then the state is switched and the in half
+//! uncovers the new screen. The overlay is `Persistent` so `global_cleanup` leaves it alone.
+use bevy::{ecs::system::Command, prelude::*, ui::FocusPolicy};
+use global_state::Persistent;
+
+use crate::state::{AppState, IntoConditionalSystem, NextState};
+
+/// seconds taken by each half of a transition
+const HALF_DURATION: f32 = 0.35;
+
+const OVERLAY_COLOR: Color = Color::BLACK;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum TransitionEffect {
+    #[default]
+    Fade,
+    /// sweeps in from the left, out to the right
+    Wipe,
+    /// closes towards the center of the screen
+    Iris,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    /// covering the old screen
+    Out,
+    /// uncovering the new screen
+    In,
+}
+
+/// The transition in progress, there is at most one at a time
+#[derive(Debug, Resource)]
+pub(crate) struct Transition {
+    to: AppState,
+    effect: TransitionEffect,
+    phase: Phase,
+    timer: Timer,
+}
+
+impl Transition {
+    fn new(to: AppState, effect: TransitionEffect) -> Self {
+        Self {
+            to,
+            effect,
+            phase: Phase::Out,
+            timer: Timer::from_seconds(HALF_DURATION, TimerMode::Once),
+        }
+    }
+
+    /// share of the screen which is covered, from 0 to 1
+    fn coverage(&self) -> f32 {
+        match self.phase {
+            Phase::Out => self.timer.percent(),
+            Phase::In => self.timer.percent_left(),
+        }
+    }
+
+    fn update(
+        mut cmd: Commands,
+        time: Res<Time>,
+        mut transition: ResMut<Transition>,
+        overlays: Query<Entity, With<TransitionOverlay>>,
+    ) {
+        if !transition.timer.tick(time.delta()).finished() {
+            return;
+        }
+
+        match transition.phase {
+            Phase::Out => {
+                cmd.insert_resource(NextState(transition.to));
+                transition.phase = Phase::In;
+                transition.timer.reset();
+            }
+            Phase::In => {
+                for overlay in overlays.iter() {
+                    cmd.entity(overlay).despawn_recursive();
+                }
+                cmd.remove_resource::<Transition>();
+            }
+        }
+    }
+
+    fn animate(
+        transition: Res<Transition>,
+        mut panels: Query<(&Panel, &mut Style, &mut BackgroundColor)>,
+    ) {
+        let coverage = transition.coverage();
+
+        for (panel, mut style, mut color) in panels.iter_mut() {
+            match (transition.effect, panel) {
+                (TransitionEffect::Fade, _) => {
+                    color.0.set_a(coverage);
+                }
+                (TransitionEffect::Wipe, _) => {
+                    style.size.width = Val::Percent(coverage * 100.);
+                    style.position.left = match transition.phase {
+                        Phase::Out => Val::Percent(0.),
+                        Phase::In => Val::Percent((1. - coverage) * 100.),
+                    };
+                }
+                (TransitionEffect::Iris, Panel::Top | Panel::Bottom) => {
+                    style.size.height = Val::Percent(coverage * 50.);
+                }
+                (TransitionEffect::Iris, _) => {
+                    style.size.width = Val::Percent(coverage * 50.);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct TransitionOverlay;
+
+/// Part of the overlay, the iris closes one panel from each side
+#[derive(Clone, Copy, Component, Debug)]
+enum Panel {
+    Whole,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Panel {
+    fn bundle(self, effect: TransitionEffect) -> NodeBundle {
+        let (position, size) = match self {
+            Self::Whole => (
+                UiRect {
+                    left: Val::Px(0.),
+                    top: Val::Px(0.),
+                    ..default()
+                },
+                Size::new(Val::Percent(100.), Val::Percent(100.)),
+            ),
+            Self::Top => (
+                UiRect {
+                    left: Val::Px(0.),
+                    top: Val::Px(0.),
+                    ..default()
+                },
+                Size::new(Val::Percent(100.), Val::Percent(0.)),
+            ),
+            Self::Bottom => (
+                UiRect {
+                    left: Val::Px(0.),
+                    bottom: Val::Px(0.),
+                    ..default()
+                },
+                Size::new(Val::Percent(100.), Val::Percent(0.)),
+            ),
+            Self::Left => (
+                UiRect {
+                    left: Val::Px(0.),
+                    top: Val::Px(0.),
+                    ..default()
+                },
+                Size::new(Val::Percent(0.), Val::Percent(100.)),
+            ),
+            Self::Right => (
+                UiRect {
+                    right: Val::Px(0.),
+                    top: Val::Px(0.),
+                    ..default()
+                },
+                Size::new(Val::Percent(0.), Val::Percent(100.)),
+            ),
+        };
+
+        let mut color = OVERLAY_COLOR;
+        if effect == TransitionEffect::Fade {
+            color.set_a(0.);
+        }
+
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position,
+                size,
+                ..default()
+            },
+            background_color: color.into(),
+            ..default()
+        }
+    }
+}
+
+/// Switches `AppState` once the out half of the effect has covered the screen
+struct StartTransition {
+    to: AppState,
+    effect: TransitionEffect,
+}
+
+impl Command for StartTransition {
+    fn write(self, world: &mut World) {
+        // buttons and timers may ask again while the screen is being covered
+        if world.contains_resource::<Transition>() {
+            return;
+        }
+
+        let panels: &[Panel] = match self.effect {
+            TransitionEffect::Fade | TransitionEffect::Wipe => &[Panel::Whole],
+            TransitionEffect::Iris => &[Panel::Top, Panel::Bottom, Panel::Left, Panel::Right],
+        };
+
+        world
+            .spawn((
+                Name::new("Transition"),
+                TransitionOverlay,
+                Persistent,
+                // drawn above every other node and keeps clicks from reaching them
+                ZIndex::Global(i32::MAX),
+                Interaction::default(),
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        ..default()
+                    },
+                    focus_policy: FocusPolicy::Block,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                for panel in panels {
+                    parent.spawn((*panel, panel.bundle(self.effect)));
+                }
+            });
+
+        world.insert_resource(Transition::new(self.to, self.effect));
+    }
+}
+
+pub(crate) trait TransitionCommandsExt {
+    /// Switch to `to` behind `effect`, ignored while another transition is running
+    fn transition_to(&mut self, to: AppState, effect: TransitionEffect);
+}
+
+impl TransitionCommandsExt for Commands<'_, '_> {
+    fn transition_to(&mut self, to: AppState, effect: TransitionEffect) {
+        self.add(StartTransition { to, effect });
+    }
+}
+
+pub(crate) struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(Transition::update.run_if_resource_exists::<Transition>())
+            .add_system(Transition::animate.run_if_resource_exists::<Transition>());
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::{prelude::*, utils::Duration};
 
 use crate::{
+    audio::sfx::{PlaySfx, Sfx},
     camera::Offscreen,
     physics::*,
     player::{Health, Player},
@@ -29,14 +30,14 @@ impl Default for WeaponCooldown {
 }
 
 #[derive(Component, Default)]
-pub(crate) struct Projectile {
+pub struct Projectile {
     damage: u32,
     started_at: Vec2,
     max_travel_distance: Option<f32>,
 }
 
 impl Projectile {
-    pub(crate) fn new(damage: u32, max_travel_distance: Option<f32>, started_at: Vec2) -> Self {
+    pub fn new(damage: u32, max_travel_distance: Option<f32>, started_at: Vec2) -> Self {
         Self {
             damage,
             max_travel_distance,
@@ -79,6 +80,7 @@ fn despawn_projectiles(
     projectiles: Query<(Entity, &Projectile, &CollidingEntities, &Transform)>,
     sensors: Query<Entity, With<Sensor>>,
     mut healths: Query<&mut Health, Without<Player>>,
+    mut sfx_events: EventWriter<PlaySfx>,
 ) {
     for (entity, projectile, colliding_entities, transform) in projectiles.iter() {
         for colliding_entity in colliding_entities.iter() {
@@ -89,6 +91,7 @@ fn despawn_projectiles(
 
             if let Ok(mut health) = healths.get_mut(colliding_entity) {
                 health.current = health.current.saturating_sub(projectile.damage);
+                sfx_events.send(PlaySfx(Sfx::Hit));
             }
         }
 
@@ -129,3 +132,27 @@ pub(crate) fn spawn_projectile(cmd: &mut Commands, translation: &Vec3, player: &
         Projectile::new(1, None, translation.truncate()),
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::ControlAction, testing::TestApp};
+
+    #[test]
+    fn projectile_kills_one_hp_mob() {
+        let mut app = TestApp::new();
+        app.spawn_ground(Vec2::new(0., 0.), Vec2::new(200., 8.));
+        let player = app.spawn_player(Vec2::new(0., 24.), 3);
+        let mob = app.spawn_mob(Vec2::new(60., 24.), 1);
+
+        app.step_seconds(0.5);
+        assert_eq!(app.world.get::<Health>(mob).map(|hp| hp.current), Some(1));
+
+        // the player faces right by default
+        app.press(player, ControlAction::Attack);
+        app.step_seconds(0.5);
+
+        assert!(app.world.get_entity(mob).is_none(), "mob is still alive");
+        assert!(app.world.get::<Player>(player).is_some());
+    }
+}